@@ -0,0 +1,99 @@
+use super::cell::*;
+use super::explorer::*;
+use super::frontier::Frontier;
+use super::point::Point;
+use arrayvec::ArrayVec;
+use core::fmt;
+
+/// 最短経路の復元に失敗した理由
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AnswerError {
+    /// どのゴール区画にもコストが設定されていない
+    GoalNotReached,
+    /// from_infoが迷路外、もしくは未到達の区画を指している
+    BrokenChain(Point),
+    /// from_infoが循環している
+    Cyclic(Point),
+    /// 経路長が格納先の容量を超えた
+    CapacityExceeded,
+}
+impl fmt::Display for AnswerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnswerError::GoalNotReached => write!(f, "goal is not reached"),
+            AnswerError::BrokenChain(p) => write!(f, "broken chain at ({}, {})", p.x, p.y),
+            AnswerError::Cyclic(p) => write!(f, "cyclic chain at ({}, {})", p.x, p.y),
+            AnswerError::CapacityExceeded => write!(f, "answer is too long"),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for AnswerError {}
+
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// ゴールからfrom_infoをstartまで辿り、経路上の区画にIS_ANSWERを立てます
+    /// 到達済のゴール区画のうち最小コストのものを終点とする
+    /// 戻り値はstartからゴールまでの順に並べた座標列で、容量Nは呼び出し側で決める
+    pub fn trace_answer<const N: usize>(&mut self) -> Result<ArrayVec<Point, N>, AnswerError> {
+        self.clear_answer();
+        let result = self.trace_answer_inner::<N>();
+        if result.is_err() {
+            // 途中まで立てたフラグは残さない
+            self.clear_answer();
+        }
+        result
+    }
+
+    /// IS_ANSWERフラグをすべて下ろします
+    pub fn clear_answer(&mut self) {
+        for row in self.cells.iter_mut() {
+            for c in row.iter_mut() {
+                c.flag.remove(CellFlag::IS_ANSWER);
+            }
+        }
+    }
+
+    fn trace_answer_inner<const N: usize>(&mut self) -> Result<ArrayVec<Point, N>, AnswerError> {
+        let mut p = self
+            .goal
+            .cells()
+            .filter(|g| g.x < W && g.y < H)
+            .filter(|g| {
+                self.cells[g.y][g.x]
+                    .flag
+                    .contains(CellFlag::IS_COST_AVAILABLE)
+            })
+            .min_by_key(|g| self.cells[g.y][g.x].cost)
+            .ok_or(AnswerError::GoalNotReached)?;
+
+        let mut dst = ArrayVec::<Point, N>::new();
+        loop {
+            // 一度通った区画に戻ってきたら循環している
+            if self.cells[p.y][p.x].flag.contains(CellFlag::IS_ANSWER) {
+                return Err(AnswerError::Cyclic(p));
+            }
+            self.cells[p.y][p.x].flag.insert(CellFlag::IS_ANSWER);
+            dst.try_push(p).map_err(|_| AnswerError::CapacityExceeded)?;
+
+            if p == self.start {
+                break;
+            }
+            // 隣接していて、コストが有効な区画を指しているはず
+            let from = self.cells[p.y][p.x].from_info.to_point();
+            if from.x >= W
+                || from.y >= H
+                || from == p
+                || from.x.abs_diff(p.x) > 1
+                || from.y.abs_diff(p.y) > 1
+                || !self.cells[from.y][from.x]
+                    .flag
+                    .contains(CellFlag::IS_COST_AVAILABLE)
+            {
+                return Err(AnswerError::BrokenChain(p));
+            }
+            p = from;
+        }
+        dst.reverse();
+        Ok(dst)
+    }
+}
@@ -1,5 +1,5 @@
-use super::direction::Direction;
 use super::search_info::SearchInfo;
+use super::wall::WallVotes;
 
 bitflags! {
     #[derive(Default)]
@@ -20,6 +20,9 @@ bitflags! {
         const IS_ANSWER = 0x00_00_00_20;
         /// Goal発見後の探索で、理想最短コストが既存のコストを上回っている場合は探索しない
         const IS_INVALIDATED = 0x00_00_00_40;
+        /// コストの根拠になった経路が、今の壁情報でも通れることを確認済
+        /// invalidate_searchの作業中だけ使う
+        const IS_COST_CHECKED = 0x00_00_00_80;
 
 
         /// 右方向の壁が存在する
@@ -44,34 +47,41 @@ pub struct Cell {
     pub from_info: SearchInfo,
     /// ステータスフラグ色々
     pub flag: CellFlag,
+    /// 上方向の壁の観測回数
+    pub up_votes: WallVotes,
+    /// 右方向の壁の観測回数
+    pub right_votes: WallVotes,
 }
 impl Default for Cell {
     fn default() -> Self {
         Self {
-            cost: std::usize::MAX,
+            cost: usize::MAX,
             from_info: SearchInfo::default(),
             flag: CellFlag::NO_FLAG,
+            up_votes: WallVotes::default(),
+            right_votes: WallVotes::default(),
         }
     }
 }
 impl Cell {
     /// コストがより良い方に更新します
     /// もし既存のコストより良いものが反映された場合stateが変更される
-    /// 新しい値が代入されたらtrueが変える
-    pub fn update_cost(&mut self, new_cost: usize, from_info: SearchInfo) {
-        self.cost = if self.flag.contains(CellFlag::IS_COST_AVAILABLE) {
+    /// 既存の値より良いコストが代入されたらtrueが返る
+    pub fn update_cost(&mut self, new_cost: usize, from_info: SearchInfo) -> bool {
+        if self.flag.contains(CellFlag::IS_COST_AVAILABLE) {
             if self.cost <= new_cost {
-                self.cost
-            } else {
-                // より小さいコストでいけるのでフラグを立てておく
-                self.flag.insert(CellFlag::IS_COST_DIRTY);
-                self.from_info = from_info;
-                new_cost
+                return false;
             }
+            // より小さいコストでいけるのでフラグを立てておく
+            self.flag.insert(CellFlag::IS_COST_DIRTY);
+            self.from_info = from_info;
+            self.cost = new_cost;
+            true
         } else {
             self.from_info = from_info;
-            new_cost
-        };
-        self.flag.insert(CellFlag::IS_COST_AVAILABLE);
+            self.cost = new_cost;
+            self.flag.insert(CellFlag::IS_COST_AVAILABLE);
+            false
+        }
     }
 }
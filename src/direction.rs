@@ -1,5 +1,5 @@
 /// 方向を示す
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Direction {
     NoDir,
     Up,
@@ -11,3 +11,103 @@ pub enum Direction {
     DownLeft,
     DownRight,
 }
+
+impl Direction {
+    /// 上下左右を上から時計回りに並べたもの
+    pub const ALL4: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+    /// 斜めを含む8方向を上から時計回りに並べたもの
+    pub const ALL8: [Direction; 8] = [
+        Direction::Up,
+        Direction::UpRight,
+        Direction::Right,
+        Direction::DownRight,
+        Direction::Down,
+        Direction::DownLeft,
+        Direction::Left,
+        Direction::UpLeft,
+    ];
+
+    /// ALL8での添字を返します。NoDirはNone
+    fn clockwise_index(self) -> Option<usize> {
+        Self::ALL8.iter().position(|&d| d == self)
+    }
+
+    /// 時計回りに45度 * steps回転した方向を返します。NoDirはNoDirのまま
+    fn rotate(self, steps: usize) -> Direction {
+        match self.clockwise_index() {
+            Some(index) => Self::ALL8[(index + steps) % Self::ALL8.len()],
+            None => Direction::NoDir,
+        }
+    }
+
+    /// 時計回りに45度回転した方向を返します
+    pub fn rotate_cw45(self) -> Direction {
+        self.rotate(1)
+    }
+    /// 反時計回りに45度回転した方向を返します
+    pub fn rotate_ccw45(self) -> Direction {
+        self.rotate(7)
+    }
+    /// 時計回りに90度回転した方向を返します
+    pub fn rotate_cw90(self) -> Direction {
+        self.rotate(2)
+    }
+    /// 反時計回りに90度回転した方向を返します
+    pub fn rotate_ccw90(self) -> Direction {
+        self.rotate(6)
+    }
+    /// 逆向きを返します
+    pub fn opposite(self) -> Direction {
+        self.rotate(4)
+    }
+
+    /// 斜め方向ならtrue
+    pub fn is_diagonal(self) -> bool {
+        matches!(
+            self,
+            Direction::UpLeft | Direction::UpRight | Direction::DownLeft | Direction::DownRight
+        )
+    }
+
+    /// この方向に1区画進んだときの(x, y)の変化量を返します。上がy+
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Direction::NoDir => (0, 0),
+            Direction::Up => (0, 1),
+            Direction::Down => (0, -1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, 1),
+            Direction::UpRight => (1, 1),
+            Direction::DownLeft => (-1, -1),
+            Direction::DownRight => (1, -1),
+        }
+    }
+
+    /// (x, y)の変化量から方向を返します。隣接していなければNoDir
+    pub fn from_delta(dx: isize, dy: isize) -> Direction {
+        Self::ALL8
+            .iter()
+            .copied()
+            .find(|d| d.delta() == (dx, dy))
+            .unwrap_or(Direction::NoDir)
+    }
+
+    /// この向きからtoの向きに変えるときの角度[度]を返します
+    /// 時計回り(右)が正で、-135から180の範囲。どちらかがNoDirならNone
+    pub fn turn_angle(self, to: Direction) -> Option<i32> {
+        let from = self.clockwise_index()?;
+        let to = to.clockwise_index()?;
+        let steps = ((to + Self::ALL8.len() - from) % Self::ALL8.len()) as i32;
+        if steps > 4 {
+            Some((steps - 8) * 45)
+        } else {
+            Some(steps * 45)
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use super::direction::Direction;
+use super::point::Point;
+use core::fmt;
+
+/// Explorerの操作に失敗した理由
+/// 実機ではdebug_assertが効かないので、Resultで受け取ってログに残す
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExplorerError {
+    /// 迷路外の座標が指定された
+    OutOfBounds(Point),
+    /// コストが未確定の区画から周辺を探索しようとした
+    CostUnavailable(Point),
+    /// 探索対象を積む容量が足りない
+    FrontierFull,
+    /// 機体の向きが上下左右のいずれでもない
+    InvalidHeading(Direction),
+    /// 確定済の壁情報では指定された区画まで移動できない
+    Unreachable(Point),
+}
+impl fmt::Display for ExplorerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExplorerError::OutOfBounds(p) => write!(f, "({}, {}) is out of the maze", p.x, p.y),
+            ExplorerError::CostUnavailable(p) => {
+                write!(f, "({}, {}) has no available cost", p.x, p.y)
+            }
+            ExplorerError::FrontierFull => write!(f, "frontier is full"),
+            ExplorerError::InvalidHeading(dir) => write!(f, "{:?} is not a valid heading", dir),
+            ExplorerError::Unreachable(p) => {
+                write!(f, "({}, {}) is unreachable with known walls", p.x, p.y)
+            }
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for ExplorerError {}
@@ -0,0 +1,199 @@
+use super::direction::Direction;
+use super::explorer::*;
+use super::frontier::Frontier;
+use super::motion::*;
+use super::point::Point;
+use arrayvec::ArrayVec;
+
+/// ターン1種類ぶんの速度と所要時間
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct TurnProfile {
+    /// ターン中の速度[mm/s]。前後の直進はこの速度で出入りする
+    pub speed: u32,
+    /// 所要時間[us]
+    pub duration: u32,
+}
+impl TurnProfile {
+    pub fn new(speed: u32, duration: u32) -> Self {
+        Self { speed, duration }
+    }
+}
+
+/// 台形加減速で走行時間を見積もるためのパラメータ
+/// マイコン上でも使えるよう、整数(mm, mm/s, mm/s^2, us)で計算する
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct VelocityProfile {
+    /// 1区画の大きさ[mm]
+    pub cell_size: u32,
+    /// 直進の最高速度[mm/s]
+    pub max_speed: u32,
+    /// 加速度[mm/s^2]
+    pub acceleration: u32,
+    /// 減速度[mm/s^2]
+    pub deceleration: u32,
+    pub search_turn90: TurnProfile,
+    pub turn90: TurnProfile,
+    pub turn180: TurnProfile,
+    pub turn45_in: TurnProfile,
+    pub turn45_out: TurnProfile,
+    pub turn135_in: TurnProfile,
+    pub turn135_out: TurnProfile,
+    pub turn_v90: TurnProfile,
+}
+impl Default for VelocityProfile {
+    /// クラシック迷路向けの目安
+    fn default() -> Self {
+        Self {
+            cell_size: 180,
+            max_speed: 2000,
+            acceleration: 5000,
+            deceleration: 5000,
+            search_turn90: TurnProfile::new(300, 236_000),
+            turn90: TurnProfile::new(700, 202_000),
+            turn180: TurnProfile::new(700, 404_000),
+            turn45_in: TurnProfile::new(700, 110_000),
+            turn45_out: TurnProfile::new(700, 110_000),
+            turn135_in: TurnProfile::new(700, 250_000),
+            turn135_out: TurnProfile::new(700, 250_000),
+            turn_v90: TurnProfile::new(700, 150_000),
+        }
+    }
+}
+
+/// 1秒[us]
+const US_PER_SEC: u64 = 1_000_000;
+/// 1mm[um]
+const UM_PER_MM: u64 = 1_000;
+/// 1/√2を65536倍したもの
+const INV_SQRT2_Q16: u64 = 46_341;
+
+/// 整数の平方根(切り捨て)
+fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    // ニュートン法。初期値は必ず解以上にする
+    let mut x = 1u64 << ((64 - n.leading_zeros()) / 2 + 1);
+    loop {
+        let next = (x + n / x) / 2;
+        if next >= x {
+            return x;
+        }
+        x = next;
+    }
+}
+
+impl VelocityProfile {
+    /// ターンの速度と所要時間を返します。直進はNone
+    pub fn turn_profile(&self, motion: &Motion) -> Option<TurnProfile> {
+        match motion {
+            Motion::Straight(_) | Motion::DiagonalStraight(_) => None,
+            Motion::SearchTurn90(_) => Some(self.search_turn90),
+            Motion::Turn90(_) => Some(self.turn90),
+            Motion::Turn180(_) => Some(self.turn180),
+            Motion::Turn45In(_) => Some(self.turn45_in),
+            Motion::Turn45Out(_) => Some(self.turn45_out),
+            Motion::Turn135In(_) => Some(self.turn135_in),
+            Motion::Turn135Out(_) => Some(self.turn135_out),
+            Motion::TurnV90(_) => Some(self.turn_v90),
+        }
+    }
+
+    /// 直進の距離[um]を返します。ターンは0
+    pub fn distance(&self, motion: &Motion) -> u64 {
+        let cell = u64::from(self.cell_size) * UM_PER_MM;
+        match motion {
+            Motion::Straight(n) => *n as u64 * cell / 2,
+            // 区画の辺から隣の辺までは1/√2区画
+            Motion::DiagonalStraight(n) => (*n as u64 * cell * INV_SQRT2_Q16) >> 16,
+            _ => 0,
+        }
+    }
+
+    /// 速度v0[mm/s]で進入し、v1[mm/s]で抜ける直進の所要時間[us]と、実際に抜ける速度を返します
+    /// 距離が足りずv1まで加減速しきれない場合は、できる限り加減速したものとする
+    pub fn straight_time(&self, distance: u64, v0: u32, v1: u32) -> (u64, u32) {
+        debug_assert!(self.max_speed > 0 && self.acceleration > 0 && self.deceleration > 0);
+        let (a, d) = (u64::from(self.acceleration), u64::from(self.deceleration));
+        let vmax = u64::from(self.max_speed);
+        let v0 = u64::from(v0).min(vmax);
+        let v1 = u64::from(v1).min(vmax);
+        if distance == 0 {
+            return (0, v0 as u32);
+        }
+
+        // 最高速度まで加速してから減速する場合の距離[um]
+        let accel_distance = (vmax * vmax - v0 * v0) * UM_PER_MM / (2 * a);
+        let decel_distance = (vmax * vmax - v1 * v1) * UM_PER_MM / (2 * d);
+        if accel_distance + decel_distance <= distance {
+            let cruise = distance - accel_distance - decel_distance;
+            let time = (vmax - v0) * US_PER_SEC / a
+                + (vmax - v1) * US_PER_SEC / d
+                + cruise * US_PER_SEC / UM_PER_MM / vmax;
+            return (time, v1 as u32);
+        }
+
+        // 最高速度に届かない三角形。加速と減速の交点が最高到達速度
+        let peak2 = (2 * a * d * distance / UM_PER_MM + d * v0 * v0 + a * v1 * v1) / (a + d);
+        if peak2 >= v0 * v0 && peak2 >= v1 * v1 {
+            let peak = isqrt(peak2);
+            let time =
+                (peak - v0.min(peak)) * US_PER_SEC / a + (peak - v1.min(peak)) * US_PER_SEC / d;
+            return (time, v1 as u32);
+        }
+
+        // 加速しきれない、もしくは減速しきれない場合は等加速度で走り抜ける
+        let v_end2 = if v0 < v1 {
+            v0 * v0 + 2 * a * distance / UM_PER_MM
+        } else {
+            (v0 * v0).saturating_sub(2 * d * distance / UM_PER_MM)
+        };
+        let v_end = isqrt(v_end2);
+        let time = 2 * distance * US_PER_SEC / UM_PER_MM / (v0 + v_end).max(1);
+        (time, v_end as u32)
+    }
+
+    /// 動作列の走行時間[us]を見積もります
+    /// 停止状態から走り出し、最後の直進で停止する
+    pub fn estimate(&self, motions: &[Motion]) -> u64 {
+        let mut total = 0;
+        let mut speed = 0;
+        for (i, motion) in motions.iter().enumerate() {
+            match self.turn_profile(motion) {
+                Some(turn) => {
+                    total += u64::from(turn.duration);
+                    speed = turn.speed;
+                }
+                None => {
+                    // 次のターンの速度まで落とす。最後なら止まる
+                    let exit_speed = match motions.get(i + 1) {
+                        Some(next) => self
+                            .turn_profile(next)
+                            .map_or(self.max_speed, |turn| turn.speed),
+                        None => 0,
+                    };
+                    let (time, end_speed) =
+                        self.straight_time(self.distance(motion), speed, exit_speed);
+                    total += time;
+                    speed = end_speed;
+                }
+            }
+        }
+        total
+    }
+}
+
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// 経路を動作列に変換して、走行時間[us]を見積もります
+    /// Nは動作列の容量。start_headingはcompile_motionと同じ
+    pub fn estimate_run_time<const N: usize>(
+        &self,
+        path: &[Point],
+        start_heading: Direction,
+        mode: MotionMode,
+        profile: &VelocityProfile,
+    ) -> Result<u64, MotionError> {
+        let motions: ArrayVec<Motion, N> = self.compile_motion(path, start_heading, mode)?;
+        Ok(profile.estimate(&motions))
+    }
+}
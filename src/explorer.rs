@@ -1,64 +1,126 @@
 use super::cell::*;
+use super::direction::Direction;
+use super::error::ExplorerError;
+use super::frontier::*;
+use super::goal::Goal;
 use super::point::Point;
-use super::search_info::*;
+use super::step_cost::StepCost;
+use super::wall::{WallPolicy, WallState};
 
+/// 迷路サイズ未指定時の幅
 pub const MAZE_WIDTH: usize = 32;
+/// 迷路サイズ未指定時の高さ
 pub const MAZE_HEIGHT: usize = 32;
 
 /// 迷路管理の親
-pub struct Explorer {
+/// W, Hで迷路サイズを指定する。16x16のクラシック迷路なら`Explorer<16, 16>`
+/// Fで探索戦略を指定する。未指定ならSearchInfoHeapによる最良優先
+pub struct Explorer<
+    const W: usize = MAZE_WIDTH,
+    const H: usize = MAZE_HEIGHT,
+    F: Frontier = SearchInfoHeap<W, H>,
+> {
     /// 開始位置
     pub start: Point,
-    /// ゴール位置
-    pub goal: Point,
+    /// 開始時の向き
+    pub start_heading: Direction,
+    /// ゴール区画
+    pub goal: Goal,
     /// 各マスごとの情報
-    pub cells: [[Cell; MAZE_WIDTH]; MAZE_HEIGHT],
+    pub cells: [[Cell; W]; H],
     /// 最短経路探索先供給
-    pub provider: SearchInfoProvider,
+    pub provider: F,
     /// 現在の最小コスト、ゴールするまではNone
     pub min_cost: Option<usize>,
+    /// 1区画移動するときのコスト。探索を始める前に設定する
+    pub step_cost: StepCost,
+    /// 観測済の壁を再観測したときの扱い
+    pub wall_policy: WallPolicy,
 }
 
-impl Default for Explorer {
+impl<const W: usize, const H: usize, F: Frontier + Default> Default for Explorer<W, H, F> {
+    /// 展開先で巨大な構造体を組み立てると、32x32の最適化ビルドが極端に遅くなるのでインライン化しない
+    #[inline(never)]
     fn default() -> Self {
         Self {
-            cells: [[Cell::default(); MAZE_WIDTH]; MAZE_HEIGHT],
+            cells: [[Cell::default(); W]; H],
             start: Point { x: 0, y: 0 },
-            goal: Point { x: 0, y: 0 },
-            provider: SearchInfoProvider::default(),
+            start_heading: Direction::Up,
+            goal: Goal::default(),
+            provider: F::default(),
             min_cost: None,
+            step_cost: StepCost::default(),
+            wall_policy: WallPolicy::default(),
         }
     }
 }
 
-impl Explorer {
-    pub fn new(goal: Point) -> Explorer {
-        let mut dst = Explorer::default();
+impl<const W: usize, const H: usize, F: Frontier + Default> Explorer<W, H, F> {
+    /// 単一区画のPoint、もしくはGoalを指定して生成します
+    /// 開始位置は左下隅(0,0)、上向きとする
+    pub fn new<G: Into<Goal>>(goal: G) -> Self {
+        Self::with_start(goal, Point { x: 0, y: 0 }, Direction::Up)
+    }
+
+    /// 開始位置と開始時の向きを指定して生成します
+    /// 開始位置が迷路外の場合はパニックする。原因を知りたい場合はtry_with_startを使う
+    pub fn with_start<G: Into<Goal>>(goal: G, start: Point, start_heading: Direction) -> Self {
+        match Self::try_with_start(goal, start, start_heading) {
+            Ok(dst) => dst,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// 開始位置と開始時の向きを指定して生成します
+    /// 開始位置が迷路外の場合はエラー
+    pub fn try_with_start<G: Into<Goal>>(
+        goal: G,
+        start: Point,
+        start_heading: Direction,
+    ) -> Result<Self, ExplorerError> {
+        if start.x >= W || start.y >= H {
+            return Err(ExplorerError::OutOfBounds(start));
+        }
+        let mut dst = Self {
+            start,
+            start_heading,
+            goal: goal.into(),
+            ..Self::default()
+        };
 
-        dst.cells = [[Cell::default(); MAZE_WIDTH]; MAZE_HEIGHT];
-        dst.start = Point { x: 0, y: 0 };
-        dst.goal = goal;
         // 上端、右端の壁初期化
-        for j in 0..MAZE_HEIGHT {
-            dst.cells[j][MAZE_WIDTH - 1]
+        for row in dst.cells.iter_mut() {
+            row[W - 1]
                 .flag
                 .insert(CellFlag::IS_EXISTS_RIGHT_WALL | CellFlag::IS_UPDATED_RIGHT_WALL);
         }
-        for i in 0..MAZE_WIDTH {
-            dst.cells[MAZE_HEIGHT - 1][i]
+        for i in 0..W {
+            dst.cells[H - 1][i]
                 .flag
                 .insert(CellFlag::IS_EXISTS_UP_WALL | CellFlag::IS_UPDATED_UP_WALL);
         }
-        // 有効コスト設定と検索対象外設定
-        dst.cells[0][0].cost = 0;
-        dst.cells[0][0]
-            .flag
-            .insert(CellFlag::IS_COST_AVAILABLE | CellFlag::IS_PROVIDER_PUSHED);
-        dst
+        // ゴール区画内の壁は存在しないものとする
+        let goal = dst.goal.clone();
+        for p in goal.cells() {
+            if p.x >= W || p.y >= H {
+                continue;
+            }
+            if p.y < H - 1 && goal.contains(Point { x: p.x, y: p.y + 1 }) {
+                dst.set_wall(p, Direction::Up, WallState::Absent);
+            }
+            if p.x < W - 1 && goal.contains(Point { x: p.x + 1, y: p.y }) {
+                dst.set_wall(p, Direction::Right, WallState::Absent);
+            }
+        }
+        // 最初の検索対象に追加
+        dst.reset_search();
+        Ok(dst)
     }
 
     /// 現在の迷路情報を出力
-    /// TODO: no_stdでの関数削除、というかもっとリッチにしろ
+    /// ファイルに書き出すのでstd featureが有効な場合のみ
+    /// TODO: もっとリッチにしろ
+    #[cfg(feature = "std")]
     pub fn debug_print(&self, filename: &str, header: &str) -> Result<(), std::io::Error> {
         const CELL_WIDTH: usize = 7;
         const CELL_HEIGHT: usize = 3;
@@ -71,43 +133,33 @@ impl Explorer {
         use std::io::prelude::*;
         use std::io::BufWriter;
         let file = OpenOptions::new()
-            .write(true)
             .append(true)
             .create(true)
-            .open(filename.to_string())?;
+            .open(filename)?;
         let mut out = BufWriter::new(file);
+        let wall_str = |state| match state {
+            WallState::Unknown => UNKNOWN_STR,
+            WallState::Present => WALL_STR,
+            WallState::Absent => NO_WALL_STR,
+        };
 
         // おしゃれなヘッダ
-        for _ in 0..(CELL_HEIGHT + 1) * MAZE_WIDTH {
+        for _ in 0..(CELL_HEIGHT + 1) * W {
             write!(out, "=")?;
         }
-        writeln!(out, "\n{}", header.to_string())?;
-        for _ in 0..(CELL_HEIGHT + 1) * MAZE_WIDTH {
+        writeln!(out, "\n{}", header)?;
+        for _ in 0..(CELL_HEIGHT + 1) * W {
             write!(out, "=")?;
         }
-        writeln!(out, "")?;
+        writeln!(out)?;
 
-        for j in 0..MAZE_HEIGHT {
+        for j in 0..H {
             //printのy方向と反転しているので注意
             // とりあえず1行書く
-            for i in 0..MAZE_WIDTH {
+            for i in 0..W {
                 write!(out, "{}", INTERSECT_STR)?;
                 // 水平壁
-                let c = if self.cells[MAZE_HEIGHT - 1 - j][i]
-                    .flag
-                    .contains(CellFlag::IS_UPDATED_UP_WALL)
-                {
-                    if self.cells[MAZE_HEIGHT - 1 - j][i]
-                        .flag
-                        .contains(CellFlag::IS_EXISTS_UP_WALL)
-                    {
-                        WALL_STR
-                    } else {
-                        NO_WALL_STR
-                    }
-                } else {
-                    UNKNOWN_STR
-                };
+                let c = wall_str(self.wall(Point { x: i, y: H - 1 - j }, Direction::Up));
                 for _ in 0..CELL_WIDTH {
                     write!(out, "{}", c)?;
                 }
@@ -116,29 +168,29 @@ impl Explorer {
             // 残りの行
             for local_j in 0..CELL_HEIGHT {
                 write!(out, "{}", WALL_STR)?; // 左端
-                for i in 0..MAZE_WIDTH {
+                for i in 0..W {
                     // 壁間の空間
                     match local_j {
-                        0 if self.cells[MAZE_HEIGHT - 1 - j][i]
+                        0 if self.cells[H - 1 - j][i]
                             .flag
                             .contains(CellFlag::IS_COST_AVAILABLE) =>
                         {
-                            write!(out, "  {:>4} ", self.cells[MAZE_HEIGHT - 1 - j][i].cost)?;
+                            write!(out, "  {:>4} ", self.cells[H - 1 - j][i].cost)?;
                         }
-                        1 if self.cells[MAZE_HEIGHT - 1 - j][i]
+                        1 if self.cells[H - 1 - j][i]
                             .flag
                             .contains(CellFlag::IS_PROVIDER_PUSHED)
-                            && self.cells[MAZE_HEIGHT - 1 - j][i].from_info.x < (MAZE_WIDTH as u8) =>
+                            && self.cells[H - 1 - j][i].from_info.x < (W as u8) =>
                         {
                             write!(
                                 out,
                                 "({:>2},{:>2})",
-                                self.cells[MAZE_HEIGHT - 1 - j][i].from_info.x,
-                                self.cells[MAZE_HEIGHT - 1 - j][i].from_info.y
+                                self.cells[H - 1 - j][i].from_info.x,
+                                self.cells[H - 1 - j][i].from_info.y
                             )?;
                         }
                         2 => {
-                            let f = self.cells[MAZE_HEIGHT - 1 - j][i].flag;
+                            let f = self.cells[H - 1 - j][i].flag;
                             write!(
                                 out,
                                 " {}{}{}{}{}{}",
@@ -181,28 +233,14 @@ impl Explorer {
                         }
                     }
                     // 垂直壁
-                    let c = if self.cells[MAZE_HEIGHT - 1 - j][i]
-                        .flag
-                        .contains(CellFlag::IS_UPDATED_RIGHT_WALL)
-                    {
-                        if self.cells[MAZE_HEIGHT - 1 - j][i]
-                            .flag
-                            .contains(CellFlag::IS_EXISTS_RIGHT_WALL)
-                        {
-                            WALL_STR
-                        } else {
-                            NO_WALL_STR
-                        }
-                    } else {
-                        UNKNOWN_STR
-                    };
+                    let c = wall_str(self.wall(Point { x: i, y: H - 1 - j }, Direction::Right));
                     write!(out, "{}", c)?;
                 }
-                writeln!(out, "")?;
+                writeln!(out)?;
             }
         }
         // 一番下
-        for _i in 0..MAZE_WIDTH {
+        for _i in 0..W {
             write!(out, "{}", INTERSECT_STR)?;
             for _ in 0..CELL_WIDTH {
                 write!(out, "{}", WALL_STR)?;
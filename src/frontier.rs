@@ -0,0 +1,300 @@
+use super::error::ExplorerError;
+use super::point::Point;
+use super::search_info::SearchInfo;
+
+/// 探索対象の区画を溜めておく入れ物
+/// 取り出し順を差し替えることで探索戦略を切り替える
+pub trait Frontier {
+    /// 格納済の数
+    fn get_count(&self) -> usize;
+    /// 追加可能な残り数
+    fn get_free(&self) -> usize;
+    /// すべて破棄します
+    fn clear(&mut self);
+    /// 区画を追加します。priorityは小さいほど優先度が高い
+    /// 戦略によっては使わない。迷路外の区画はOutOfBounds、容量不足はFrontierFull
+    fn try_push(&mut self, p: Point, priority: usize) -> Result<(), ExplorerError>;
+    /// 次の区画を取り出します
+    fn pop(&mut self) -> Option<Point>;
+    /// 格納済の区画ならtrue。trueの区画を再度積んでも容量を消費しない
+    /// 重複して積む戦略では常にfalse
+    fn contains(&self, _p: Point) -> bool {
+        false
+    }
+
+    /// try_pushの結果をboolで返します。容量不足で追加できなければfalse
+    fn push(&mut self, p: Point, priority: usize) -> bool {
+        let is_pushed = self.try_push(p, priority).is_ok();
+        // 無理だけど普通にfalse返すだけで良さげ
+        debug_assert!(is_pushed);
+        is_pushed
+    }
+}
+
+/// pがW * Hの迷路内か確認します
+fn check_bounds<const W: usize, const H: usize>(p: Point) -> Result<(), ExplorerError> {
+    if p.x < W && p.y < H {
+        Ok(())
+    } else {
+        Err(ExplorerError::OutOfBounds(p))
+    }
+}
+
+/// Stackにして深さ優先、追加履歴が可能な限り近いところから取り出す
+/// 容量は迷路の区画数(W * H)と同じで、get_freeが0になるまで積める。const genericsで積を書けないので二次元配列で確保する
+pub struct SearchInfoProvider<const W: usize, const H: usize> {
+    pub datas: [[SearchInfo; W]; H],
+    pub wr_ptr: usize,
+}
+impl<const W: usize, const H: usize> Default for SearchInfoProvider<W, H> {
+    fn default() -> Self {
+        Self {
+            datas: [[SearchInfo::default(); W]; H],
+            wr_ptr: 0,
+        }
+    }
+}
+impl<const W: usize, const H: usize> SearchInfoProvider<W, H> {
+    /// 格納可能な最大数
+    pub const CAPACITY: usize = W * H;
+}
+impl<const W: usize, const H: usize> Frontier for SearchInfoProvider<W, H> {
+    fn get_count(&self) -> usize {
+        self.wr_ptr
+    }
+    fn get_free(&self) -> usize {
+        Self::CAPACITY - self.wr_ptr
+    }
+    fn clear(&mut self) {
+        self.wr_ptr = 0;
+    }
+    /// 優先度は使わない。呼び出し側で優先度の低い順に積むこと
+    fn try_push(&mut self, p: Point, _priority: usize) -> Result<(), ExplorerError> {
+        check_bounds::<W, H>(p)?;
+        if self.wr_ptr < Self::CAPACITY {
+            self.datas[self.wr_ptr / W][self.wr_ptr % W] = SearchInfo::from(p);
+            self.wr_ptr += 1;
+            Ok(())
+        } else {
+            Err(ExplorerError::FrontierFull)
+        }
+    }
+    fn pop(&mut self) -> Option<Point> {
+        if self.wr_ptr > 0 {
+            self.wr_ptr -= 1; // read有効データは書き込み先のひとつ下
+            let data = self.datas[self.wr_ptr / W][self.wr_ptr % W];
+            Some(data.to_point())
+        } else {
+            None
+        }
+    }
+}
+
+/// リングバッファにして幅優先、追加順に取り出す
+/// 容量はSearchInfoProviderと同じくW * H
+pub struct SearchInfoQueue<const W: usize, const H: usize> {
+    pub datas: [[SearchInfo; W]; H],
+    pub rd_ptr: usize,
+    pub count: usize,
+}
+impl<const W: usize, const H: usize> Default for SearchInfoQueue<W, H> {
+    fn default() -> Self {
+        Self {
+            datas: [[SearchInfo::default(); W]; H],
+            rd_ptr: 0,
+            count: 0,
+        }
+    }
+}
+impl<const W: usize, const H: usize> SearchInfoQueue<W, H> {
+    /// 格納可能な最大数
+    pub const CAPACITY: usize = W * H;
+}
+impl<const W: usize, const H: usize> Frontier for SearchInfoQueue<W, H> {
+    fn get_count(&self) -> usize {
+        self.count
+    }
+    fn get_free(&self) -> usize {
+        Self::CAPACITY - self.count
+    }
+    fn clear(&mut self) {
+        self.rd_ptr = 0;
+        self.count = 0;
+    }
+    /// 優先度は使わない
+    fn try_push(&mut self, p: Point, _priority: usize) -> Result<(), ExplorerError> {
+        check_bounds::<W, H>(p)?;
+        if self.count < Self::CAPACITY {
+            let wr_ptr = (self.rd_ptr + self.count) % Self::CAPACITY;
+            self.datas[wr_ptr / W][wr_ptr % W] = SearchInfo::from(p);
+            self.count += 1;
+            Ok(())
+        } else {
+            Err(ExplorerError::FrontierFull)
+        }
+    }
+    fn pop(&mut self) -> Option<Point> {
+        if self.count > 0 {
+            let data = self.datas[self.rd_ptr / W][self.rd_ptr % W];
+            self.rd_ptr = (self.rd_ptr + 1) % Self::CAPACITY;
+            self.count -= 1;
+            Some(data.to_point())
+        } else {
+            None
+        }
+    }
+}
+
+/// SearchInfoHeapの要素
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SearchInfoHeapEntry {
+    pub info: SearchInfo,
+    /// 優先度。小さいほど先に取り出す
+    pub key: u32,
+    /// 追加順。keyが同じ場合は後から追加したものを先に取り出す
+    pub seq: u32,
+}
+impl SearchInfoHeapEntry {
+    fn is_prior_to(&self, other: &SearchInfoHeapEntry) -> bool {
+        self.key < other.key || (self.key == other.key && self.seq > other.seq)
+    }
+}
+
+/// 二分ヒープにして最良優先、追加時に指定した優先度が最小のものから取り出す
+/// 格納済の区画を再度積んだ場合は、重複させずに優先度を付け替える(decrease-key)
+/// 同じ区画は1つしか入らないので、容量はSearchInfoProviderと同じくW * Hで足りる
+pub struct SearchInfoHeap<const W: usize, const H: usize> {
+    pub datas: [[SearchInfoHeapEntry; W]; H],
+    /// 区画ごとのdatas上の位置。区画数は32x32程度なのでu16に詰める
+    /// 古い値が残っていることがあるので、datas側の区画と一致するかで判断する
+    pub positions: [[u16; W]; H],
+    pub count: usize,
+    pub seq: u32,
+}
+impl<const W: usize, const H: usize> Default for SearchInfoHeap<W, H> {
+    fn default() -> Self {
+        debug_assert!(W * H <= usize::from(u16::MAX) + 1);
+        Self {
+            datas: [[SearchInfoHeapEntry::default(); W]; H],
+            positions: [[0; W]; H],
+            count: 0,
+            seq: 0,
+        }
+    }
+}
+impl<const W: usize, const H: usize> Frontier for SearchInfoHeap<W, H> {
+    fn get_count(&self) -> usize {
+        self.count
+    }
+    fn get_free(&self) -> usize {
+        Self::CAPACITY - self.count
+    }
+    fn clear(&mut self) {
+        self.count = 0;
+        self.seq = 0;
+    }
+    /// 格納済の区画は、新しい優先度に付け替えて後から追加したものとして扱う
+    fn try_push(&mut self, p: Point, priority: usize) -> Result<(), ExplorerError> {
+        check_bounds::<W, H>(p)?;
+        let entry = SearchInfoHeapEntry {
+            info: SearchInfo::from(p),
+            key: priority.min(u32::MAX as usize) as u32,
+            seq: self.seq,
+        };
+        let index = match self.position(p) {
+            Some(index) => index,
+            None if self.count < Self::CAPACITY => {
+                // 末尾に追加する
+                self.count += 1;
+                self.count - 1
+            }
+            None => return Err(ExplorerError::FrontierFull),
+        };
+        self.seq = self.seq.wrapping_add(1);
+        self.set(index, entry);
+        self.sift_up(index);
+        self.sift_down(index);
+        Ok(())
+    }
+    fn pop(&mut self) -> Option<Point> {
+        self.pop_entry().map(|e| e.info.to_point())
+    }
+    fn contains(&self, p: Point) -> bool {
+        self.position(p).is_some()
+    }
+}
+impl<const W: usize, const H: usize> SearchInfoHeap<W, H> {
+    /// 格納可能な最大数
+    pub const CAPACITY: usize = W * H;
+
+    /// 優先度付きで取り出します
+    pub fn pop_entry(&mut self) -> Option<SearchInfoHeapEntry> {
+        if self.count == 0 {
+            return None;
+        }
+        let dst = self.get(0);
+        self.count -= 1;
+        // 末尾を根に置いて子と入れ替えていく
+        if self.count > 0 {
+            self.set(0, self.get(self.count));
+            self.sift_down(0);
+        }
+        Some(dst)
+    }
+
+    /// 格納済の区画のdatas上の位置
+    fn position(&self, p: Point) -> Option<usize> {
+        if p.x >= W || p.y >= H {
+            return None;
+        }
+        let index = usize::from(self.positions[p.y][p.x]);
+        if index < self.count && self.get(index).info.to_point() == p {
+            Some(index)
+        } else {
+            None
+        }
+    }
+    /// 親より優先なら入れ替えていく
+    fn sift_up(&mut self, mut index: usize) {
+        let entry = self.get(index);
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if !entry.is_prior_to(&self.get(parent)) {
+                break;
+            }
+            self.set(index, self.get(parent));
+            index = parent;
+        }
+        self.set(index, entry);
+    }
+    /// 子の方が優先なら入れ替えていく
+    fn sift_down(&mut self, mut index: usize) {
+        let entry = self.get(index);
+        loop {
+            let left = index * 2 + 1;
+            let right = left + 1;
+            if left >= self.count {
+                break;
+            }
+            let child = if right < self.count && self.get(right).is_prior_to(&self.get(left)) {
+                right
+            } else {
+                left
+            };
+            if !self.get(child).is_prior_to(&entry) {
+                break;
+            }
+            self.set(index, self.get(child));
+            index = child;
+        }
+        self.set(index, entry);
+    }
+    fn get(&self, index: usize) -> SearchInfoHeapEntry {
+        self.datas[index / W][index % W]
+    }
+    fn set(&mut self, index: usize, entry: SearchInfoHeapEntry) {
+        self.datas[index / W][index % W] = entry;
+        let p = entry.info.to_point();
+        self.positions[p.y][p.x] = index as u16;
+    }
+}
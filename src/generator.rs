@@ -0,0 +1,193 @@
+use super::direction::Direction;
+use super::goal::Goal;
+use super::maze::Maze;
+use super::point::Point;
+
+/// 迷路生成用の疑似乱数(xorshift64)
+/// 同じseedなら同じ系列を返す
+#[derive(Copy, Clone, Debug)]
+pub struct XorShift {
+    state: u64,
+}
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // 0だと0しか出てこなくなる
+        Self {
+            state: if seed == 0 {
+                0x9e37_79b9_7f4a_7c15
+            } else {
+                seed
+            },
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// 0..nの値を返します
+    pub fn gen_range(&mut self, n: usize) -> usize {
+        debug_assert!(n > 0);
+        (self.next_u64() % (n as u64)) as usize
+    }
+}
+
+/// 上下左右の順
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+impl<const W: usize, const H: usize> Maze<W, H> {
+    /// 競技規定に沿った迷路を生成します。同じseedなら同じ迷路になる
+    /// - 開始位置は(0,0)で右に壁があり、上に抜ける
+    /// - 中央の区画(幅、高さが偶数なら2x2)がゴールで、入口は1か所
+    /// - ゴール中央を除くすべての柱に1枚以上の壁がある
+    ///
+    /// loopsが0なら閉路のない迷路、それ以外は最大loops枚の壁を追加で取り除く
+    pub fn generate(seed: u64, loops: usize) -> Self {
+        debug_assert!(W >= 4 && H >= 4);
+        let mut rng = XorShift::new(seed);
+        let goal_min = Point {
+            x: (W - 1) / 2,
+            y: (H - 1) / 2,
+        };
+        let goal_max = Point { x: W / 2, y: H / 2 };
+        let goal = Goal::rect(goal_min, goal_max);
+        let start = Point { x: 0, y: 0 };
+
+        // すべての壁がある状態から掘っていく
+        let mut dst = Self::new(start, goal.clone());
+        for y in 0..H {
+            for x in 0..W {
+                dst.set_wall(Point { x, y }, Direction::Up, true);
+                dst.set_wall(Point { x, y }, Direction::Right, true);
+            }
+        }
+        // ゴール区画内の壁を取り除き、掘り済とする
+        let mut visited = [[false; W]; H];
+        for p in goal.cells() {
+            visited[p.y][p.x] = true;
+            if p.x < goal_max.x {
+                dst.set_wall(p, Direction::Right, false);
+            }
+            if p.y < goal_max.y {
+                dst.set_wall(p, Direction::Up, false);
+            }
+        }
+
+        // 開始区画からは上にだけ抜け、そこから深さ優先で掘る
+        // 戻り先は掘ってきた方向で覚えておく
+        let mut from = [[Direction::NoDir; W]; H];
+        visited[start.y][start.x] = true;
+        dst.set_wall(start, Direction::Up, false);
+        let mut p = start.get_around(Direction::Up);
+        visited[p.y][p.x] = true;
+        loop {
+            let mut candidates = [Direction::NoDir; 4];
+            let mut count = 0;
+            for &dir in DIRECTIONS.iter() {
+                if let Some(next) = p.neighbor::<W, H>(dir) {
+                    if !visited[next.y][next.x] {
+                        candidates[count] = dir;
+                        count += 1;
+                    }
+                }
+            }
+            if count > 0 {
+                let dir = candidates[rng.gen_range(count)];
+                dst.set_wall(p, dir, false);
+                p = p.get_around(dir);
+                visited[p.y][p.x] = true;
+                from[p.y][p.x] = dir.opposite();
+            } else {
+                match from[p.y][p.x] {
+                    Direction::NoDir => break,
+                    dir => p = p.get_around(dir),
+                }
+            }
+        }
+
+        // ゴールの入口を1か所だけ開ける
+        let mut entrances = [(start, Direction::NoDir); 16];
+        let mut count = 0;
+        for g in goal.cells() {
+            for &dir in DIRECTIONS.iter() {
+                if let Some(next) = g.neighbor::<W, H>(dir) {
+                    if !goal.contains(next) && count < entrances.len() {
+                        entrances[count] = (g, dir);
+                        count += 1;
+                    }
+                }
+            }
+        }
+        if count > 0 {
+            let (g, dir) = entrances[rng.gen_range(count)];
+            dst.set_wall(g, dir, false);
+        }
+
+        // 柱の壁がなくならない範囲で、ランダムに壁を取り除いて閉路を作る
+        let mut removed = 0;
+        for _ in 0..loops.saturating_mul(16) {
+            if removed >= loops {
+                break;
+            }
+            let p = Point {
+                x: rng.gen_range(W),
+                y: rng.gen_range(H),
+            };
+            let dir = if rng.gen_range(2) == 0 {
+                Direction::Up
+            } else {
+                Direction::Right
+            };
+            let next = match p.neighbor::<W, H>(dir) {
+                Some(next) => next,
+                None => continue,
+            };
+            if !dst.has_wall(p, dir)
+                || goal.contains(p) != goal.contains(next)
+                || (p == start && dir == Direction::Right)
+            {
+                continue;
+            }
+            // 壁の両端の柱
+            let posts = match dir {
+                Direction::Up => [(p.x, p.y + 1), (p.x + 1, p.y + 1)],
+                _ => [(p.x + 1, p.y), (p.x + 1, p.y + 1)],
+            };
+            if posts.iter().all(|&(px, py)| dst.post_walls(px, py) > 1) {
+                dst.set_wall(p, dir, false);
+                removed += 1;
+            }
+        }
+        dst
+    }
+
+    /// 柱(px, py)につながっている壁の数を返します
+    /// 柱の座標は区画(x, y)の左下を(x, y)とし、外周の柱は4を返す
+    pub fn post_walls(&self, px: usize, py: usize) -> usize {
+        if px == 0 || py == 0 || px >= W || py >= H {
+            return 4;
+        }
+        let lower_left = Point {
+            x: px - 1,
+            y: py - 1,
+        };
+        let upper_right = Point { x: px, y: py };
+        [
+            self.has_wall(lower_left, Direction::Up),
+            self.has_wall(lower_left, Direction::Right),
+            self.has_wall(upper_right, Direction::Down),
+            self.has_wall(upper_right, Direction::Left),
+        ]
+        .iter()
+        .filter(|&&exists| exists)
+        .count()
+    }
+}
@@ -0,0 +1,123 @@
+use super::point::Point;
+use super::search_info::SearchInfo;
+use super::step_cost::StepCost;
+use arrayvec::ArrayVec;
+
+/// 任意区画指定でのゴール区画数の上限。3x3のゴールが収まれば十分
+pub const GOAL_CELLS_MAX: usize = 16;
+
+/// ゴール区画の集合
+/// いずれかの区画に到達した時点でゴールとみなす
+#[derive(Clone, Debug)]
+pub enum Goal {
+    /// min, maxを両端に含む矩形範囲
+    Rect { min: Point, max: Point },
+    /// 任意の区画の列挙。Explorerに持たせるのでSearchInfoで圧縮しておく
+    Cells(ArrayVec<SearchInfo, GOAL_CELLS_MAX>),
+}
+impl Default for Goal {
+    fn default() -> Self {
+        Goal::from(Point::default())
+    }
+}
+impl From<Point> for Goal {
+    fn from(p: Point) -> Self {
+        Goal::Rect { min: p, max: p }
+    }
+}
+
+impl Goal {
+    /// 2点を対角とする矩形のゴールを作成します
+    pub fn rect(a: Point, b: Point) -> Goal {
+        Goal::Rect {
+            min: Point {
+                x: a.x.min(b.x),
+                y: a.y.min(b.y),
+            },
+            max: Point {
+                x: a.x.max(b.x),
+                y: a.y.max(b.y),
+            },
+        }
+    }
+    /// 任意の区画の集合からゴールを作成します
+    /// 空、もしくはGOAL_CELLS_MAXを超える場合はNone
+    pub fn from_cells(cells: &[Point]) -> Option<Goal> {
+        if cells.is_empty() || cells.len() > GOAL_CELLS_MAX {
+            return None;
+        }
+        let mut dst = ArrayVec::<SearchInfo, GOAL_CELLS_MAX>::new();
+        for p in cells {
+            if !dst.iter().any(|c| c.to_point() == *p) {
+                dst.push(SearchInfo::from(*p));
+            }
+        }
+        Some(Goal::Cells(dst))
+    }
+    /// ゴール区画に含まれていればtrue
+    pub fn contains(&self, p: Point) -> bool {
+        match self {
+            Goal::Rect { min, max } => min.x <= p.x && p.x <= max.x && min.y <= p.y && p.y <= max.y,
+            Goal::Cells(cells) => cells.iter().any(|c| c.to_point() == p),
+        }
+    }
+    /// 最寄りのゴール区画までの移動コストの推定値を返します
+    pub fn estimate(&self, p: Point, step_cost: &StepCost) -> usize {
+        self.nearest_by(p, |g| step_cost.distance(g, p))
+    }
+    /// ゴール区画からpへの距離のうち最小のものを返します
+    /// 距離は座標の差が小さいほど小さくなるものとする
+    fn nearest_by(&self, p: Point, distance: impl Fn(Point) -> usize) -> usize {
+        match self {
+            Goal::Rect { min, max } => {
+                // 矩形内に丸めた点が最寄り
+                let nearest = Point {
+                    x: p.x.max(min.x).min(max.x),
+                    y: p.y.max(min.y).min(max.y),
+                };
+                distance(nearest)
+            }
+            Goal::Cells(cells) => cells
+                .iter()
+                .map(|c| distance(c.to_point()))
+                .min()
+                .unwrap_or(usize::MAX),
+        }
+    }
+    /// ゴール区画を列挙します
+    pub fn cells(&self) -> GoalCells<'_> {
+        GoalCells {
+            goal: self,
+            index: 0,
+        }
+    }
+}
+
+/// Goal::cellsで使うイテレータ
+pub struct GoalCells<'a> {
+    goal: &'a Goal,
+    index: usize,
+}
+impl<'a> Iterator for GoalCells<'a> {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        let dst = match self.goal {
+            Goal::Rect { min, max } => {
+                let w = max.x - min.x + 1;
+                let h = max.y - min.y + 1;
+                if self.index < w * h {
+                    Some(Point {
+                        x: min.x + self.index % w,
+                        y: min.y + self.index / w,
+                    })
+                } else {
+                    None
+                }
+            }
+            Goal::Cells(cells) => cells.get(self.index).map(|c| c.to_point()),
+        };
+        self.index += 1;
+        dst
+    }
+}
@@ -1,13 +1,29 @@
-// #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(feature = "std"), no_std)]
 #[macro_use]
 extern crate bitflags;
 extern crate arrayvec;
 
+pub mod answer;
 pub mod explorer;
 pub mod prelude;
 
 pub mod cell;
 pub mod direction;
+pub mod error;
+pub mod estimator;
+pub mod frontier;
+pub mod generator;
+pub mod goal;
+pub mod maz;
+pub mod maze;
+pub mod mazefile;
+pub mod motion;
+pub mod observation;
+pub mod planner;
 pub mod point;
 pub mod search_info;
+pub mod simulator;
+pub mod step_cost;
+pub mod step_map;
 pub mod update_info;
+pub mod wall;
@@ -1,250 +1,168 @@
 extern crate oratosquilla;
 
-// #[cfg(test)]
-mod test {
-    use oratosquilla::prelude::*;
-
-    /// 最初の地点から一歩進むか
-    #[test]
-    pub fn ahead_start() {
-        let mut m = Explorer::new(Point { x: 10, y: 10 });
-
-        let p = Point { x: 0, y: 0 };
-        let mut info = UpdateInfo::default();
-        info.p = p;
-        info.up = Some(false);
-        info.down = None;
-        info.left = Some(true);
-        info.right = Some(true);
-        m.update(&info);
-        m.fetch_targets(p);
-        let next_p = m.get_next();
-        m.debug_print("test.log", "ahead_start").unwrap();
-
-        assert_eq!(next_p.is_some(), true);
-        assert_eq!(next_p.unwrap().x, 0);
-        assert_eq!(next_p.unwrap().y, 1);
+use oratosquilla::prelude::*;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "usage: oratosquilla <command> [args]
+
+commands:
+    solve <maze>          print the optimal path and its cost
+    simulate <maze>       explore the maze and print statistics
+    render <maze>         print the maze in the mazefiles format
+    convert <in> <out>    convert between .maz and the mazefiles format
+
+<maze> is a 16x16 or 32x32 maze, binary if the extension is .maz, mazefiles text otherwise";
+
+/// 読み込んだ迷路ファイル。形式は拡張子で判断する
+enum MazeData {
+    /// mazefiles形式
+    Text(String),
+    /// .maz形式
+    Maz(Vec<u8>),
+}
+impl MazeData {
+    fn is_maz(path: &str) -> bool {
+        path.to_ascii_lowercase().ends_with(".maz")
     }
 
-    /// 最初の地点で2マス更新をかけた際に、斜めマスを最短とするか
-    /// .++++.++++.????
-    /// +         +    
-    /// +   1    1+    
-    /// .    .++++.????
-    /// + SS +    ?    
-    /// +   0+    ?    
-    /// .++++.++++.++++
-    #[test]
-    pub fn diagonal_start() {
-        let mut m = Explorer::new(Point { x: 10, y: 10 });
-
-        let p = Point { x: 0, y: 0 };
-        let mut info = UpdateInfo::default();
-        info.p = p;
-        info.up = Some(false);
-        info.down = None;
-        info.left = Some(true);
-        info.right = Some(true);
-        m.update(&info);
-
-        let mut info = UpdateInfo::default();
-        info.p = Point { x: 0, y: 1 };
-        info.up = Some(true);
-        info.down = None;
-        info.left = Some(true);
-        info.right = Some(false);
-        m.update(&info);
-
-        m.fetch_targets(p);
-        let next_p = m.get_next();
-        m.debug_print("test.log", "diagonal_start").unwrap();
-
-        assert_eq!(next_p.is_some(), true);
-        assert_eq!(next_p.unwrap().x, 1);
-        assert_eq!(next_p.unwrap().y, 1);
+    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let data = if Self::is_maz(path) {
+            MazeData::Maz(fs::read(path)?)
+        } else {
+            MazeData::Text(fs::read_to_string(path)?)
+        };
+        Ok(data)
     }
 
-    /// 最初の地点で2マス更新をかけた際に、斜めマスを最短としてさらに探索を続けるか
-    ///        ↓ここ
-    /// .????.????.????.????.
-    /// +    ?    ?    ?    ?
-    /// +    ?   2?    ?    ?
-    /// .++++.    .????.????.
-    /// +              ?    ?
-    /// +   1    1    2?    ?
-    /// .    .    .????.????.
-    /// + SS +    ?    ?    ?
-    /// +   0+   2?    ?    ?
-    /// .++++.++++.++++.++++.
-    #[test]
-    pub fn diagonal_start_2() {
-        let mut m = Explorer::new(Point { x: 10, y: 10 });
-
-        let p = Point { x: 0, y: 0 };
-        let mut info = UpdateInfo::default();
-        info.p = p;
-        info.up = Some(false);
-        info.down = None;
-        info.left = Some(true);
-        info.right = Some(true);
-        m.update(&info);
-
-        let mut info = UpdateInfo::default();
-        info.p = Point { x: 0, y: 1 };
-        info.up = Some(true);
-        info.down = None;
-        info.left = Some(true);
-        info.right = Some(false);
-        m.update(&info);
-
-        m.fetch_targets(p);
-        let next_p = m.get_next().unwrap();
-        info.p = next_p;
-        info.up = Some(false);
-        info.down = Some(false);
-        info.left = None;
-        info.right = Some(false);
-        m.update(&info);
-        m.fetch_targets(next_p);
-
-        let last_p = m.get_next();
-        m.debug_print("test.log", "diagonal_start_2").unwrap();
-
-        assert_eq!(last_p.is_some(), true);
-        // 上が最短になるはず
-        assert_eq!(last_p.unwrap().x, 1);
-        assert_eq!(last_p.unwrap().y, 2);
+    /// 迷路の幅、高さ
+    fn size(&self) -> Option<(usize, usize)> {
+        match self {
+            MazeData::Text(text) => mazefile_size(text),
+            MazeData::Maz(data) => match data.len() {
+                256 => Some((16, 16)),
+                1024 => Some((32, 32)),
+                _ => None,
+            },
+        }
     }
 
-    /// ゴールしてかつ他に探索可能区間がない場合に探索が止まるか
-    #[test]
-    pub fn goal_and_stop() {
-        let mut m = Explorer::new(Point { x: 0, y: 1 });
-
-        let p = Point { x: 0, y: 0 };
-        let mut info = UpdateInfo::default();
-        info.p = p;
-        info.up = Some(false);
-        info.down = None;
-        info.left = Some(true);
-        info.right = Some(true);
-        m.update(&info);
-        m.fetch_targets(p);
-
-        let next_p = m.get_next().unwrap();
-        info.p = next_p;
-        info.up = Some(true);
-        info.down = None;
-        info.left = Some(true);
-        info.right = Some(true);
-        m.update(&info);
-        m.fetch_targets(next_p);
-
-        let last_p = m.get_next();
-        m.debug_print("test.log", "goal_and_stop").unwrap();
-
-        assert_eq!(last_p.is_some(), false);
+    fn to_maze<const W: usize, const H: usize>(&self) -> Result<Maze<W, H>, Box<dyn Error>> {
+        let maze = match self {
+            MazeData::Text(text) => Maze::from_mazefile(text)?,
+            MazeData::Maz(data) => Maze::from_maz(data)?,
+        };
+        Ok(maze)
     }
+}
 
-    /// 一切壁がなく右端がゴールの場合に最短距離ですすめるか
-    #[test]
-    pub fn no_wall_move_x() {
-        let mut m = Explorer::new(Point {
-            x: MAZE_WIDTH - 1,
-            y: 0,
-        });
-
-        let mut p = Point { x: 0, y: 0 };
-        for _i in 0..MAZE_WIDTH - 1 {
-            let mut info = UpdateInfo::default();
-            info.p = p;
-            info.up = Some(false);
-            info.down = Some(false);
-            info.left = Some(false);
-            info.right = Some(false);
-            m.update(&info);
-            m.fetch_targets(p);
-            p = m.get_next().unwrap();
-            // m.debug_print("no_wall_move_x.log", "index").unwrap();
-        }
-        m.debug_print("test.log", "no_wall_move_x").unwrap();
+/// 壁情報がすべて既知の状態で最短経路を求めます
+/// Nは最短経路の最大長で、区画数W * Hを指定する
+fn solve<const W: usize, const H: usize, const N: usize>(
+    maze: &Maze<W, H>,
+) -> Result<(), Box<dyn Error>> {
+    let mut m = Explorer::<W, H>::from_maze(maze);
+    while let Some(p) = m.get_next() {
+        m.try_fetch_targets(p)?;
+    }
+    let path = m.trace_answer::<N>()?;
+    println!("cost: {}", m.min_cost.unwrap_or_default());
+    for p in path {
+        println!("{} {}", p.x, p.y);
+    }
+    Ok(())
+}
 
-        assert_eq!(p.x, MAZE_WIDTH - 1);
-        assert_eq!(p.y, 0);
+/// 壁を観測しながら探索させ、結果を表示します
+fn simulate<const W: usize, const H: usize>(maze: &Maze<W, H>) -> Result<(), Box<dyn Error>> {
+    let mut sim = Simulator::<W, H>::new(maze.clone());
+    let report = sim.run()?;
+    match report.goal_steps {
+        Some(steps) => println!("goal reached: {} steps, {} cells", steps, report.distance),
+        None => println!("goal not reached"),
+    }
+    let report = sim.run_to_end()?;
+    println!(
+        "search finished: {} steps, {} cells",
+        report.steps, report.distance
+    );
+    match report.min_cost {
+        Some(cost) => println!("cost: {}", cost),
+        None => println!("cost: -"),
     }
+    Ok(())
+}
 
-    /// 一切壁がなく上端がゴールの場合に最短距離ですすめるか
-    #[test]
-    pub fn no_wall_move_y() {
-        let mut m = Explorer::new(Point {
-            x: 0,
-            y: MAZE_HEIGHT - 1,
-        });
-
-        let mut p = Point { x: 0, y: 0 };
-        for _i in 0..MAZE_HEIGHT - 1 {
-            let mut info = UpdateInfo::default();
-            info.p = p;
-            info.up = Some(false);
-            info.down = Some(false);
-            info.left = Some(false);
-            info.right = Some(false);
-            m.update(&info);
-            m.fetch_targets(p);
-            p = m.get_next().unwrap();
-            // m.debug_print("no_wall_move_y.log", "index").unwrap();
-        }
-        m.debug_print("test.log", "no_wall_move_y").unwrap();
+fn render<const W: usize, const H: usize>(maze: &Maze<W, H>) -> Result<(), Box<dyn Error>> {
+    let mut text = String::new();
+    maze.write_mazefile(&mut text)?;
+    print!("{}", text);
+    Ok(())
+}
 
-        assert_eq!(p.x, 0);
-        assert_eq!(p.y, MAZE_HEIGHT - 1);
+fn convert<const W: usize, const H: usize>(
+    maze: &Maze<W, H>,
+    path: &str,
+) -> Result<(), Box<dyn Error>> {
+    if MazeData::is_maz(path) {
+        let mut data = vec![0; W * H];
+        maze.to_maz(&mut data)?;
+        fs::write(path, data)?;
+    } else {
+        let mut text = String::new();
+        maze.write_mazefile(&mut text)?;
+        fs::write(path, text)?;
+    }
+    Ok(())
+}
+
+/// Nは最短経路の最大長で、区画数W * Hを指定する
+fn execute<const W: usize, const H: usize, const N: usize>(
+    command: &str,
+    data: &MazeData,
+    paths: &[String],
+) -> Result<(), Box<dyn Error>> {
+    let maze = data
+        .to_maze::<W, H>()
+        .map_err(|e| format!("{}: {}", paths[0], e))?;
+    match command {
+        "solve" => solve::<W, H, N>(&maze),
+        "simulate" => simulate(&maze),
+        "render" => render(&maze),
+        _ => convert(&maze, &paths[1]),
+    }
+}
+
+fn run(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let (command, paths) = match args.split_first() {
+        Some((command, paths)) => (command.as_str(), paths),
+        None => return Err(USAGE.into()),
+    };
+    let expected = match command {
+        "solve" | "simulate" | "render" => 1,
+        "convert" => 2,
+        _ => return Err(USAGE.into()),
+    };
+    if paths.len() != expected {
+        return Err(USAGE.into());
     }
-    /// 一切壁がなく右上端がゴールの場合に最短距離ですすめるか
-    #[test]
-    pub fn no_wall_move_xy() {
-        let mut m = Explorer::new(Point {
-            x: MAZE_WIDTH - 1,
-            y: MAZE_HEIGHT - 1,
-        });
-
-        let mut p = Point { x: 0, y: 0 };
-        for _i in 0..31 {
-            let mut info = UpdateInfo::default();
-            info.p = p;
-            info.up = Some(false);
-            info.down = Some(false);
-            info.left = Some(false);
-            info.right = Some(false);
-            m.update(&info);
-            // 斜め走行を最初から出すためには隣のマスの情報が必要
-            if p.x < MAZE_WIDTH - 1 {
-                info.p = Point { x: p.x + 1, y: p.y };
-                info.up = Some(false);
-                info.down = Some(false);
-                info.left = Some(false);
-                info.right = Some(p.x >= MAZE_WIDTH - 2);
-                m.update(&info);
-            }
-            if p.y < MAZE_HEIGHT - 1 {
-                info.p = Point { x: p.x, y: p.y + 1 };
-                info.up = Some(p.y >= MAZE_HEIGHT - 2);
-                info.down = Some(false);
-                info.left = Some(false);
-                info.right = Some(false);
-                m.update(&info);
-            }
-
-            m.fetch_targets(p);
-            p = m.get_next().unwrap();
-            // m.debug_print("no_wall_move_xy.log", "index").unwrap();
-        }
-        m.debug_print("test.log", "no_wall_move_xy").unwrap();
 
-        assert_eq!(p.x, MAZE_WIDTH - 1);
-        assert_eq!(p.y, MAZE_HEIGHT - 1);
+    // 迷路サイズはconst genericsなので、対応しているサイズごとに分岐する
+    let data = MazeData::read(&paths[0])?;
+    match data.size() {
+        Some((16, 16)) => execute::<16, 16, { 16 * 16 }>(command, &data, paths),
+        Some((32, 32)) => execute::<32, 32, { 32 * 32 }>(command, &data, paths),
+        Some((w, h)) => Err(format!("{}: unsupported maze size {}x{}", paths[0], w, h).into()),
+        None => Err(format!("{}: unknown maze format", paths[0]).into()),
     }
 }
+
 fn main() {
-    // use oratosquilla::prelude::*;
-    // test::no_wall_move_xy();
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,155 @@
+//! 1区画1byteのバイナリ形式(.maz)
+//! 16x16なら256byte、32x32なら1024byteになる
+//! 区画の並びは左下から上方向(x * H + y)、壁はbit0から北、東、南、西
+
+use super::direction::Direction;
+use super::explorer::Explorer;
+use super::frontier::Frontier;
+use super::goal::Goal;
+use super::maze::Maze;
+use super::point::Point;
+use super::wall::WallState;
+use core::fmt;
+
+bitflags! {
+    #[derive(Default)]
+    pub struct MazCell: u8 {
+        const NORTH = 0x01;
+        const EAST = 0x02;
+        const SOUTH = 0x04;
+        const WEST = 0x08;
+    }
+}
+
+/// .mazの読み書きに失敗した理由
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MazError {
+    /// データ長がW * Hと一致しない
+    InvalidLength(usize),
+    /// pのdir方向の壁が隣の区画と食い違っている。外周の壁がない場合も含む
+    Inconsistent { p: Point, dir: Direction },
+}
+impl fmt::Display for MazError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MazError::InvalidLength(len) => write!(f, "invalid .maz length {}", len),
+            MazError::Inconsistent { p, dir } => {
+                write!(f, "inconsistent wall at ({}, {}) {:?}", p.x, p.y, dir)
+            }
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for MazError {}
+
+/// 区画pの格納位置
+fn maz_index<const H: usize>(p: Point) -> usize {
+    p.x * H + p.y
+}
+
+/// 区画ごとの壁を聞きながら書き込みます
+fn write_maz<const W: usize, const H: usize>(
+    dst: &mut [u8],
+    wall: impl Fn(Point, Direction) -> bool,
+) -> Result<(), MazError> {
+    if dst.len() != W * H {
+        return Err(MazError::InvalidLength(dst.len()));
+    }
+    for x in 0..W {
+        for y in 0..H {
+            let p = Point { x, y };
+            let mut c = MazCell::empty();
+            c.set(MazCell::NORTH, wall(p, Direction::Up));
+            c.set(MazCell::EAST, wall(p, Direction::Right));
+            c.set(MazCell::SOUTH, wall(p, Direction::Down));
+            c.set(MazCell::WEST, wall(p, Direction::Left));
+            dst[maz_index::<H>(p)] = c.bits();
+        }
+    }
+    Ok(())
+}
+
+impl<const W: usize, const H: usize> Maze<W, H> {
+    /// .mazから読み込みます。開始位置は(0,0)、ゴールは中央の区画とする
+    /// 隣り合う区画で壁の有無が異なる場合はエラー
+    pub fn from_maz(src: &[u8]) -> Result<Self, MazError> {
+        if src.len() != W * H {
+            return Err(MazError::InvalidLength(src.len()));
+        }
+        let mut dst = Self::new(
+            Point::default(),
+            Goal::rect(
+                Point {
+                    x: (W - 1) / 2,
+                    y: (H - 1) / 2,
+                },
+                Point { x: W / 2, y: H / 2 },
+            ),
+        );
+        let get = |p: Point| MazCell::from_bits_truncate(src[maz_index::<H>(p)]);
+        for x in 0..W {
+            for y in 0..H {
+                let p = Point { x, y };
+                let c = get(p);
+                // 上、右の壁を隣の下、左の壁と突き合わせる。外周は隣を壁ありとみなす
+                let up = if y < H - 1 {
+                    get(Point { x, y: y + 1 }).contains(MazCell::SOUTH)
+                } else {
+                    true
+                };
+                let right = if x < W - 1 {
+                    get(Point { x: x + 1, y }).contains(MazCell::WEST)
+                } else {
+                    true
+                };
+                if c.contains(MazCell::NORTH) != up {
+                    return Err(MazError::Inconsistent {
+                        p,
+                        dir: Direction::Up,
+                    });
+                }
+                if c.contains(MazCell::EAST) != right {
+                    return Err(MazError::Inconsistent {
+                        p,
+                        dir: Direction::Right,
+                    });
+                }
+                if y == 0 && !c.contains(MazCell::SOUTH) {
+                    return Err(MazError::Inconsistent {
+                        p,
+                        dir: Direction::Down,
+                    });
+                }
+                if x == 0 && !c.contains(MazCell::WEST) {
+                    return Err(MazError::Inconsistent {
+                        p,
+                        dir: Direction::Left,
+                    });
+                }
+                dst.set_wall(p, Direction::Up, up);
+                dst.set_wall(p, Direction::Right, right);
+            }
+        }
+        Ok(dst)
+    }
+
+    /// .mazに書き出します。dstの長さはW * H
+    pub fn to_maz(&self, dst: &mut [u8]) -> Result<(), MazError> {
+        write_maz::<W, H>(dst, |p, dir| self.has_wall(p, dir))
+    }
+}
+
+impl<const W: usize, const H: usize, F: Frontier + Default> Explorer<W, H, F> {
+    /// .mazから壁情報がすべて既知のExplorerを生成します
+    pub fn from_maz(src: &[u8]) -> Result<Self, MazError> {
+        Ok(Self::from_maze(&Maze::from_maz(src)?))
+    }
+}
+
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// 現在の壁情報を.mazに書き出します。dstの長さはW * H
+    /// .mazには未確定を表現できないので、未確定の壁は存在するものとする
+    pub fn to_maz(&self, dst: &mut [u8]) -> Result<(), MazError> {
+        write_maz::<W, H>(dst, |p, dir| self.wall(p, dir) != WallState::Absent)
+    }
+}
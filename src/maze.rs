@@ -0,0 +1,109 @@
+use super::direction::Direction;
+use super::explorer::*;
+use super::frontier::*;
+use super::goal::Goal;
+use super::point::Point;
+use super::update_info::UpdateInfo;
+
+bitflags! {
+    #[derive(Default)]
+    pub struct MazeWall: u8 {
+        /// 上方向の壁が存在する
+        const UP = 0x01;
+        /// 右方向の壁が存在する
+        const RIGHT = 0x02;
+    }
+}
+
+/// 壁情報がすべて分かっている迷路(正解データ)
+/// シミュレータに隠しておいたり、Explorerの初期値に使う
+#[derive(Clone, Debug)]
+pub struct Maze<const W: usize, const H: usize> {
+    /// 各区画の上、右の壁。下、左の壁は隣の区画に格納されている
+    pub walls: [[MazeWall; W]; H],
+    /// 開始位置
+    pub start: Point,
+    /// ゴール区画
+    pub goal: Goal,
+}
+impl<const W: usize, const H: usize> Default for Maze<W, H> {
+    /// 外周の壁だけがある迷路
+    fn default() -> Self {
+        let mut dst = Self {
+            walls: [[MazeWall::default(); W]; H],
+            start: Point::default(),
+            goal: Goal::default(),
+        };
+        for row in dst.walls.iter_mut() {
+            row[W - 1].insert(MazeWall::RIGHT);
+        }
+        for wall in dst.walls[H - 1].iter_mut() {
+            wall.insert(MazeWall::UP);
+        }
+        dst
+    }
+}
+
+impl<const W: usize, const H: usize> Maze<W, H> {
+    /// 外周の壁だけがある迷路を生成します
+    pub fn new<G: Into<Goal>>(start: Point, goal: G) -> Self {
+        Self {
+            start,
+            goal: goal.into(),
+            ..Self::default()
+        }
+    }
+
+    /// 壁が格納されている区画とフラグを返します。外周はNone
+    fn wall_index(p: Point, dir: Direction) -> Option<(Point, MazeWall)> {
+        let neighbor = p.neighbor::<W, H>(dir)?;
+        match dir {
+            Direction::Up => Some((p, MazeWall::UP)),
+            Direction::Right => Some((p, MazeWall::RIGHT)),
+            Direction::Down => Some((neighbor, MazeWall::UP)),
+            Direction::Left => Some((neighbor, MazeWall::RIGHT)),
+            _ => None,
+        }
+    }
+
+    /// pからdir方向(上下左右)に壁があればtrue。外周は常にtrue
+    pub fn has_wall(&self, p: Point, dir: Direction) -> bool {
+        debug_assert!(p.x < W && p.y < H);
+        match Self::wall_index(p, dir) {
+            Some((cell, flag)) => self.walls[cell.y][cell.x].contains(flag),
+            None => true,
+        }
+    }
+
+    /// pからdir方向(上下左右)の壁を設定します。外周は変更できない
+    pub fn set_wall(&mut self, p: Point, dir: Direction, exists: bool) {
+        debug_assert!(p.x < W && p.y < H);
+        if let Some((cell, flag)) = Self::wall_index(p, dir) {
+            self.walls[cell.y][cell.x].set(flag, exists);
+        }
+    }
+
+    /// 区画pに入った際に観測できる壁情報を返します
+    pub fn get_update_info(&self, p: Point) -> UpdateInfo {
+        UpdateInfo {
+            p,
+            up: Some(self.has_wall(p, Direction::Up)),
+            down: Some(self.has_wall(p, Direction::Down)),
+            left: Some(self.has_wall(p, Direction::Left)),
+            right: Some(self.has_wall(p, Direction::Right)),
+        }
+    }
+}
+
+impl<const W: usize, const H: usize, F: Frontier + Default> Explorer<W, H, F> {
+    /// 壁情報がすべて既知のExplorerを生成します
+    pub fn from_maze(maze: &Maze<W, H>) -> Self {
+        let mut dst = Self::with_start(maze.goal.clone(), maze.start, Direction::Up);
+        for y in 0..H {
+            for x in 0..W {
+                dst.update(&maze.get_update_info(Point { x, y }));
+            }
+        }
+        dst
+    }
+}
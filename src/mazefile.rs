@@ -0,0 +1,312 @@
+//! micromouseonlineのmazefilesで使われているテキスト形式
+//!
+//! ```text
+//! o---o---o
+//! | G     |
+//! o   o---o
+//! | S |   |
+//! o---o---o
+//! ```
+//! 柱は`o`(`+`、`.`も可)、横壁は`---`、縦壁は`|`で、1区画が横4文字、縦2行になる
+//! 区画内の`S`が開始位置、`G`がゴール区画を示す
+//! Explorerから書き出す場合、未確定の壁は`?`になる。Explorerで読み込むと未確定のまま戻る
+
+use super::direction::Direction;
+use super::explorer::Explorer;
+use super::frontier::Frontier;
+use super::goal::{Goal, GOAL_CELLS_MAX};
+use super::maze::Maze;
+use super::point::Point;
+use super::wall::WallState;
+use arrayvec::ArrayVec;
+use core::fmt;
+
+/// 1区画あたりの文字数(横)
+const CELL_COLUMNS: usize = 4;
+/// 1区画あたりの行数
+const CELL_LINES: usize = 2;
+/// 未確定の壁を表す文字
+pub const MAZEFILE_UNKNOWN: char = '?';
+
+/// 読み込みに失敗した理由
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MazefileErrorKind {
+    /// 柱、壁として解釈できない文字
+    UnexpectedChar(char),
+    /// 行数、もしくは1行の文字数が迷路サイズと一致しない
+    SizeMismatch,
+    /// 開始位置が複数ある
+    MultipleStart,
+    /// ゴール区画がGOAL_CELLS_MAXを超えた
+    TooManyGoals,
+}
+
+/// 読み込みに失敗した位置と理由。行、列は1始まり
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MazefileError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: MazefileErrorKind,
+}
+impl fmt::Display for MazefileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match self.kind {
+            MazefileErrorKind::UnexpectedChar(c) => write!(f, "unexpected character '{}'", c),
+            MazefileErrorKind::SizeMismatch => write!(f, "maze size mismatch"),
+            MazefileErrorKind::MultipleStart => write!(f, "multiple start cells"),
+            MazefileErrorKind::TooManyGoals => write!(f, "too many goal cells"),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for MazefileError {}
+
+/// テキストから迷路の幅、高さを読み取ります
+/// 1行目の文字数と行数から求める。形式として成り立たなければNone
+pub fn mazefile_size(text: &str) -> Option<(usize, usize)> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let columns = lines.next()?.trim_end().chars().count();
+    let line_count = 1 + lines.count();
+    if columns < CELL_COLUMNS + 1
+        || (columns - 1) % CELL_COLUMNS != 0
+        || line_count < CELL_LINES + 1
+        || (line_count - 1) % CELL_LINES != 0
+    {
+        return None;
+    }
+    Some(((columns - 1) / CELL_COLUMNS, (line_count - 1) / CELL_LINES))
+}
+
+fn is_post(c: char) -> bool {
+    c == 'o' || c == '+' || c == '.'
+}
+
+impl<const W: usize, const H: usize> Maze<W, H> {
+    /// mazefiles形式のテキストから迷路を読み込みます
+    /// `S`がなければ(0,0)、`G`がなければ中央の区画をゴールとする
+    /// 外周の壁、および未確定の壁(MAZEFILE_UNKNOWN)は存在するものとして扱う
+    pub fn from_mazefile(text: &str) -> Result<Self, MazefileError> {
+        let mut dst = Self::default();
+        let (start, goal) = Self::read_walls(text, |p, dir, exists| {
+            dst.set_wall(p, dir, exists.unwrap_or(true))
+        })?;
+        dst.start = start;
+        dst.goal = goal;
+        Ok(dst)
+    }
+
+    /// mazefiles形式のテキストを読み、外周以外の上、左の壁の状態をset_wallに渡します
+    /// 未確定の壁(MAZEFILE_UNKNOWN)はNoneで渡す
+    /// 開始位置とゴール区画を返す。`S`がなければ(0,0)、`G`がなければ中央の区画とする
+    fn read_walls(
+        text: &str,
+        mut set_wall: impl FnMut(Point, Direction, Option<bool>),
+    ) -> Result<(Point, Goal), MazefileError> {
+        let mut start = None;
+        let mut goals = ArrayVec::<Point, GOAL_CELLS_MAX>::new();
+
+        // 空行は読み飛ばすが、エラー位置は元の行番号で返す
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+        for r in 0..(H * CELL_LINES + 1) {
+            let (line_index, line) = lines.next().ok_or(MazefileError {
+                line: text.lines().count() + 1,
+                column: 1,
+                kind: MazefileErrorKind::SizeMismatch,
+            })?;
+            let error = |column: usize, kind| MazefileError {
+                line: line_index + 1,
+                column: column + 1,
+                kind,
+            };
+            if line.trim_end().chars().count() > W * CELL_COLUMNS + 1 {
+                return Err(error(W * CELL_COLUMNS + 1, MazefileErrorKind::SizeMismatch));
+            }
+            // 末尾の空白は削られていることがあるので、足りない分は空白とみなす
+            let mut chars = line.chars().chain(core::iter::repeat(' '));
+            let mut next_char = || chars.next().unwrap_or(' ');
+
+            // 偶数行は柱と横壁、奇数行は縦壁と区画内。上の行ほどyが大きい
+            let row = r / CELL_LINES;
+            for x in 0..=W {
+                let column = x * CELL_COLUMNS;
+                let c = next_char();
+                if r % CELL_LINES == 0 {
+                    if !is_post(c) {
+                        return Err(error(column, MazefileErrorKind::UnexpectedChar(c)));
+                    }
+                    if x == W {
+                        break;
+                    }
+                    let mut exists = None;
+                    for offset in 1..CELL_COLUMNS {
+                        let c = next_char();
+                        let is_wall = match c {
+                            '-' => Some(true),
+                            ' ' => Some(false),
+                            MAZEFILE_UNKNOWN => None,
+                            _ => {
+                                return Err(error(
+                                    column + offset,
+                                    MazefileErrorKind::UnexpectedChar(c),
+                                ))
+                            }
+                        };
+                        if *exists.get_or_insert(is_wall) != is_wall {
+                            return Err(error(
+                                column + offset,
+                                MazefileErrorKind::UnexpectedChar(c),
+                            ));
+                        }
+                    }
+                    // 外周は渡さない
+                    if row > 0 && row < H {
+                        let p = Point { x, y: H - 1 - row };
+                        set_wall(p, Direction::Up, exists.unwrap_or(Some(false)));
+                    }
+                } else {
+                    let exists = match c {
+                        '|' => Some(true),
+                        ' ' => Some(false),
+                        MAZEFILE_UNKNOWN => None,
+                        _ => return Err(error(column, MazefileErrorKind::UnexpectedChar(c))),
+                    };
+                    if x == W {
+                        break;
+                    }
+                    let p = Point { x, y: H - 1 - row };
+                    if x > 0 {
+                        set_wall(p, Direction::Left, exists);
+                    }
+                    // 区画内の目印
+                    for offset in 1..CELL_COLUMNS {
+                        match next_char() {
+                            'S' | 's' => {
+                                if start.is_some() {
+                                    return Err(error(
+                                        column + offset,
+                                        MazefileErrorKind::MultipleStart,
+                                    ));
+                                }
+                                start = Some(p);
+                            }
+                            'G' | 'g' if !goals.contains(&p) => {
+                                goals.try_push(p).map_err(|_| {
+                                    error(column + offset, MazefileErrorKind::TooManyGoals)
+                                })?;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        if let Some((line_index, _)) = lines.next() {
+            return Err(MazefileError {
+                line: line_index + 1,
+                column: 1,
+                kind: MazefileErrorKind::SizeMismatch,
+            });
+        }
+
+        let goal = match Goal::from_cells(&goals) {
+            Some(goal) => goal,
+            None => Goal::rect(
+                Point {
+                    x: (W - 1) / 2,
+                    y: (H - 1) / 2,
+                },
+                Point { x: W / 2, y: H / 2 },
+            ),
+        };
+        Ok((start.unwrap_or_default(), goal))
+    }
+}
+
+/// 上、右の壁の状態を聞きながらmazefiles形式で書き出します
+/// 未確定の壁はNoneを返すとMAZEFILE_UNKNOWNで書かれる
+fn write_walls<Wr, const W: usize, const H: usize>(
+    out: &mut Wr,
+    start: Point,
+    goal: &Goal,
+    wall: impl Fn(Point, Direction) -> Option<bool>,
+) -> fmt::Result
+where
+    Wr: fmt::Write,
+{
+    let wall_char = |state: Option<bool>, c: char| match state {
+        Some(true) => c,
+        Some(false) => ' ',
+        None => MAZEFILE_UNKNOWN,
+    };
+    for y in (0..H).rev() {
+        // 柱と横壁
+        for x in 0..W {
+            let c = wall_char(wall(Point { x, y }, Direction::Up), '-');
+            write!(out, "o{}{}{}", c, c, c)?;
+        }
+        writeln!(out, "o")?;
+        // 縦壁と区画内
+        write!(out, "|")?;
+        for x in 0..W {
+            let p = Point { x, y };
+            let marker = if p == start {
+                'S'
+            } else if goal.contains(p) {
+                'G'
+            } else {
+                ' '
+            };
+            let c = wall_char(wall(p, Direction::Right), '|');
+            write!(out, " {} {}", marker, c)?;
+        }
+        writeln!(out)?;
+    }
+    for _ in 0..W {
+        write!(out, "o---")?;
+    }
+    writeln!(out, "o")
+}
+
+impl<const W: usize, const H: usize> Maze<W, H> {
+    /// mazefiles形式で書き出します
+    pub fn write_mazefile<Wr: fmt::Write>(&self, out: &mut Wr) -> fmt::Result {
+        write_walls::<Wr, W, H>(out, self.start, &self.goal, |p, dir| {
+            Some(self.has_wall(p, dir))
+        })
+    }
+}
+
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// mazefiles形式のテキストから壁情報を読み込んで生成します
+    /// 未確定の壁(MAZEFILE_UNKNOWN)は未確定のままにする。開始時の向きは上とする
+    pub fn from_mazefile(text: &str) -> Result<Self, MazefileError>
+    where
+        F: Default,
+    {
+        // 生成にゴールが必要なので、先に開始位置とゴールだけ読んでおく
+        let (start, goal) = Maze::<W, H>::read_walls(text, |_, _, _| {})?;
+        let mut dst = Self::with_start(goal, start, Direction::Up);
+        Maze::<W, H>::read_walls(text, |p, dir, exists| {
+            if let Some(exists) = exists {
+                dst.set_wall(p, dir, WallState::from_exists(exists));
+            }
+        })?;
+        Ok(dst)
+    }
+
+    /// 現在の壁情報をmazefiles形式で書き出します
+    /// 未確定の壁はMAZEFILE_UNKNOWNになるので、ビューアで開く場合は置き換えること
+    /// from_mazefileで読み込めば未確定のまま戻る
+    pub fn write_mazefile<Wr: fmt::Write>(&self, out: &mut Wr) -> fmt::Result {
+        write_walls::<Wr, W, H>(out, self.start, &self.goal, |p, dir| {
+            match self.wall(p, dir) {
+                WallState::Unknown => None,
+                state => Some(state == WallState::Present),
+            }
+        })
+    }
+}
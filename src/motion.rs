@@ -0,0 +1,424 @@
+use super::direction::Direction;
+use super::explorer::*;
+use super::frontier::Frontier;
+use super::point::Point;
+use arrayvec::ArrayVec;
+
+/// 旋回の向き
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Turn {
+    Left,
+    Right,
+}
+
+/// モータ制御側が実行する動作
+/// 各ターンは前後の半区画の直進を含み、Straightには含まれない
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Motion {
+    /// 半区画単位の直進
+    Straight(usize),
+    /// 斜めの直進。区画の辺から隣の辺までを1とする
+    DiagonalStraight(usize),
+    /// 1区画内で曲がる探索用の90度ターン。前後の直進は含まない
+    SearchTurn90(Turn),
+    /// 大回り90度ターン
+    Turn90(Turn),
+    /// 大回り180度ターン
+    Turn180(Turn),
+    /// 直進から斜めに入る45度ターン
+    Turn45In(Turn),
+    /// 斜めから直進に戻る45度ターン
+    Turn45Out(Turn),
+    /// 直進から斜めに入る135度ターン
+    Turn135In(Turn),
+    /// 斜めから直進に戻る135度ターン
+    Turn135Out(Turn),
+    /// 斜めから斜めへのV字90度ターン
+    TurnV90(Turn),
+}
+
+/// 使う動作の種類
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MotionMode {
+    /// SearchTurn90と直進のみ
+    Search,
+    /// 大回りターンを使い、斜めは使わない
+    Slalom,
+    /// 斜め走行を含むすべての動作を使う
+    Diagonal,
+}
+
+/// 動作列に変換できない理由
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MotionError {
+    /// 迷路外の座標が含まれている
+    OutOfBounds(Point),
+    /// 隣接していない座標に移動しようとした
+    NotAdjacent(Point),
+    /// 既知の壁情報では通れない
+    Blocked(Point),
+    /// 来た方向に引き返している
+    Reversal(Point),
+    /// 同じ向きに3回以上続けて曲がるなど、対応する動作がない
+    Unrealizable(Point),
+    /// 動作列が格納先の容量を超えた
+    CapacityExceeded,
+}
+
+/// 区画単位の動作。直進する区画は半区画2つに分ける
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Token {
+    Half,
+    Turn(Turn),
+}
+
+/// 経路を上下左右の移動に分解しながら、Tokenを列挙する
+struct Tokens<'a, const W: usize, const H: usize, F: Frontier> {
+    explorer: &'a Explorer<W, H, F>,
+    path: &'a [Point],
+    /// 次に出発するpathの添字
+    index: usize,
+    /// 現在の区画
+    cell: Point,
+    /// 斜め移動の2手目
+    pending: Option<Direction>,
+    /// 直前の移動方向
+    heading: Option<Direction>,
+    /// 開始区画での機体の向き。NoDirなら最初の移動方向に合わせる
+    start_heading: Direction,
+    queue: ArrayVec<(Token, Point), 2>,
+    is_finished: bool,
+}
+
+impl<'a, const W: usize, const H: usize, F: Frontier> Tokens<'a, W, H, F> {
+    fn new(explorer: &'a Explorer<W, H, F>, path: &'a [Point], start_heading: Direction) -> Self {
+        Self {
+            explorer,
+            path,
+            index: 0,
+            cell: path.first().copied().unwrap_or_default(),
+            pending: None,
+            heading: None,
+            start_heading,
+            queue: ArrayVec::new(),
+            is_finished: path.len() < 2,
+        }
+    }
+
+    /// 次の上下左右の移動を、出発する区画と方向で返します
+    fn next_move(&mut self) -> Result<Option<(Point, Direction)>, MotionError> {
+        let from = self.cell;
+        if let Some(dir) = self.pending.take() {
+            self.cell = from.get_around(dir);
+            self.index += 1;
+            return Ok(Some((from, dir)));
+        }
+        if self.index + 1 >= self.path.len() {
+            return Ok(None);
+        }
+        let to = self.path[self.index + 1];
+        if to.x >= W || to.y >= H {
+            return Err(MotionError::OutOfBounds(to));
+        }
+        let is_passable = |a: Point, b: Point| self.explorer.get_passable_arounds(a).contains(&b);
+
+        let vertical = match to.y as isize - from.y as isize {
+            1 => Some(Direction::Up),
+            -1 => Some(Direction::Down),
+            0 => None,
+            _ => return Err(MotionError::NotAdjacent(to)),
+        };
+        let horizontal = match to.x as isize - from.x as isize {
+            1 => Some(Direction::Right),
+            -1 => Some(Direction::Left),
+            0 => None,
+            _ => return Err(MotionError::NotAdjacent(to)),
+        };
+        match (vertical, horizontal) {
+            (Some(dir), None) | (None, Some(dir)) => {
+                if !is_passable(from, to) {
+                    return Err(MotionError::Blocked(from));
+                }
+                self.cell = to;
+                self.index += 1;
+                Ok(Some((from, dir)))
+            }
+            (Some(vertical), Some(horizontal)) => {
+                // 迂回ルートのうち、今の向きと違う方へ先に曲がる方を優先する
+                // 斜めが続くと左右交互に曲がる形になり、斜め走行にまとめられる
+                // 開始区画では開始時の向きに進む方を優先する
+                let is_horizontal_first = match self.heading {
+                    Some(heading) => heading == vertical,
+                    None => self.start_heading == horizontal,
+                };
+                let routes = if is_horizontal_first {
+                    [(horizontal, vertical), (vertical, horizontal)]
+                } else {
+                    [(vertical, horizontal), (horizontal, vertical)]
+                };
+                let (first, second) = routes
+                    .iter()
+                    .copied()
+                    .find(|&(first, _)| {
+                        let corner = from.get_around(first);
+                        is_passable(from, corner) && is_passable(corner, to)
+                    })
+                    .ok_or(MotionError::Blocked(from))?;
+                self.cell = from.get_around(first);
+                self.pending = Some(second);
+                Ok(Some((from, first)))
+            }
+            (None, None) => Err(MotionError::NotAdjacent(to)),
+        }
+    }
+}
+
+impl<'a, const W: usize, const H: usize, F: Frontier> Iterator for Tokens<'a, W, H, F> {
+    type Item = Result<(Token, Point), MotionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if !self.queue.is_empty() {
+                return Some(Ok(self.queue.remove(0)));
+            }
+            if self.is_finished {
+                return None;
+            }
+            match self.next_move() {
+                Ok(Some((cell, dir))) => {
+                    match self.heading {
+                        // 開始区画の中心から辺まで。その場で旋回する動作はない
+                        None if self.start_heading != Direction::NoDir
+                            && self.start_heading != dir =>
+                        {
+                            self.is_finished = true;
+                            return Some(Err(MotionError::Unrealizable(cell)));
+                        }
+                        None => self.queue.push((Token::Half, cell)),
+                        Some(prev) => match prev.turn_angle(dir) {
+                            Some(0) => {
+                                self.queue.push((Token::Half, cell));
+                                self.queue.push((Token::Half, cell));
+                            }
+                            Some(90) => self.queue.push((Token::Turn(Turn::Right), cell)),
+                            Some(-90) => self.queue.push((Token::Turn(Turn::Left), cell)),
+                            _ => {
+                                self.is_finished = true;
+                                return Some(Err(MotionError::Reversal(cell)));
+                            }
+                        },
+                    }
+                    self.heading = Some(dir);
+                }
+                Ok(None) => {
+                    // ゴール区画の辺から中心まで
+                    self.is_finished = true;
+                    self.queue.push((Token::Half, self.cell));
+                }
+                Err(e) => {
+                    self.is_finished = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// パターンの照合に使う先読み
+struct Lookahead<I> {
+    iter: I,
+    buf: ArrayVec<(Token, Point), 3>,
+}
+impl<I: Iterator<Item = Result<(Token, Point), MotionError>>> Lookahead<I> {
+    fn peek(&mut self, k: usize) -> Result<Option<Token>, MotionError> {
+        while self.buf.len() <= k {
+            match self.iter.next() {
+                Some(token) => self.buf.push(token?),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(self.buf[k].0))
+    }
+
+    /// 先頭のTokenがある区画
+    fn cell(&self) -> Point {
+        self.buf.first().map(|&(_, p)| p).unwrap_or_default()
+    }
+
+    fn consume(&mut self, n: usize) {
+        for _ in 0..n {
+            self.buf.remove(0);
+        }
+    }
+}
+
+/// 動作列の組み立て
+struct Program<const N: usize> {
+    motions: ArrayVec<Motion, N>,
+    /// まだ動作にしていない半区画の数
+    straight: usize,
+}
+impl<const N: usize> Program<N> {
+    /// たまっている直進を動作にします
+    fn flush(&mut self) -> Result<(), MotionError> {
+        if self.straight > 0 {
+            self.motions
+                .try_push(Motion::Straight(self.straight))
+                .map_err(|_| MotionError::CapacityExceeded)?;
+            self.straight = 0;
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, motion: Motion) -> Result<(), MotionError> {
+        self.flush()?;
+        self.motions
+            .try_push(motion)
+            .map_err(|_| MotionError::CapacityExceeded)
+    }
+
+    /// 直前の半区画を含むターンを追加します
+    fn push_turn(&mut self, motion: Motion, cell: Point) -> Result<(), MotionError> {
+        self.straight = self
+            .straight
+            .checked_sub(1)
+            .ok_or(MotionError::Unrealizable(cell))?;
+        self.push(motion)
+    }
+}
+
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// 隣接する区画を結ぶ経路を、走行用の動作列に変換します
+    /// 斜めの移動は既知の壁情報から迂回ルートを選ぶ
+    /// 開始区画の中心からstart_headingを向いて走り出し、ゴール区画の中心で止まる
+    /// 最初の移動方向がstart_headingと違えばUnrealizable。NoDirなら向きを問わない
+    pub fn compile_motion<const N: usize>(
+        &self,
+        path: &[Point],
+        start_heading: Direction,
+        mode: MotionMode,
+    ) -> Result<ArrayVec<Motion, N>, MotionError> {
+        if let Some(&p) = path.iter().find(|p| p.x >= W || p.y >= H) {
+            return Err(MotionError::OutOfBounds(p));
+        }
+        let mut tokens = Lookahead {
+            iter: Tokens::new(self, path, start_heading),
+            buf: ArrayVec::new(),
+        };
+        let mut program = Program::<N> {
+            motions: ArrayVec::new(),
+            straight: 0,
+        };
+
+        while let Some(token) = tokens.peek(0)? {
+            let cell = tokens.cell();
+            let turn = match token {
+                Token::Half => {
+                    program.straight += 1;
+                    tokens.consume(1);
+                    continue;
+                }
+                Token::Turn(turn) => turn,
+            };
+            let next = tokens.peek(1)?;
+            let next2 = tokens.peek(2)?;
+            let same = Some(Token::Turn(turn));
+
+            match mode {
+                MotionMode::Search => {
+                    program.push(Motion::SearchTurn90(turn))?;
+                    tokens.consume(1);
+                }
+                MotionMode::Slalom => {
+                    if next == Some(Token::Half) && program.straight > 0 {
+                        program.push_turn(Motion::Turn90(turn), cell)?;
+                        tokens.consume(2);
+                    } else if next == same && next2 == Some(Token::Half) && program.straight > 0 {
+                        program.push_turn(Motion::Turn180(turn), cell)?;
+                        tokens.consume(3);
+                    } else {
+                        program.push(Motion::SearchTurn90(turn))?;
+                        tokens.consume(1);
+                    }
+                }
+                MotionMode::Diagonal => {
+                    if next == Some(Token::Half) {
+                        program.push_turn(Motion::Turn90(turn), cell)?;
+                        tokens.consume(2);
+                        continue;
+                    }
+                    if next == same {
+                        match next2 {
+                            Some(Token::Half) => {
+                                program.push_turn(Motion::Turn180(turn), cell)?;
+                                tokens.consume(3);
+                                continue;
+                            }
+                            Some(Token::Turn(t)) if t != turn => {
+                                program.push_turn(Motion::Turn135In(turn), cell)?;
+                                tokens.consume(2);
+                            }
+                            _ => return Err(MotionError::Unrealizable(cell)),
+                        }
+                    } else {
+                        program.push_turn(Motion::Turn45In(turn), cell)?;
+                        tokens.consume(1);
+                    }
+                    Self::compile_diagonal(&mut tokens, &mut program)?;
+                }
+            }
+        }
+        program.flush()?;
+        Ok(program.motions)
+    }
+
+    /// 斜めに入った直後から、直進に戻るまでを変換します
+    fn compile_diagonal<I, const N: usize>(
+        tokens: &mut Lookahead<I>,
+        program: &mut Program<N>,
+    ) -> Result<(), MotionError>
+    where
+        I: Iterator<Item = Result<(Token, Point), MotionError>>,
+    {
+        let mut diagonal = 0;
+        loop {
+            let token = tokens.peek(0)?;
+            let cell = tokens.cell();
+            let turn = match token {
+                Some(Token::Turn(turn)) => turn,
+                _ => return Err(MotionError::Unrealizable(cell)),
+            };
+            let same = Some(Token::Turn(turn));
+            let next = tokens.peek(1)?;
+            let next2 = tokens.peek(2)?;
+
+            // 左右交互に曲がっている間は斜めの直進
+            if next != same && next != Some(Token::Half) {
+                diagonal += 1;
+                tokens.consume(1);
+                continue;
+            }
+            if diagonal > 0 {
+                program.push(Motion::DiagonalStraight(diagonal))?;
+                diagonal = 0;
+            }
+            if next == Some(Token::Half) {
+                program.push(Motion::Turn45Out(turn))?;
+                tokens.consume(2);
+                return Ok(());
+            }
+            match next2 {
+                Some(Token::Half) => {
+                    program.push(Motion::Turn135Out(turn))?;
+                    tokens.consume(3);
+                    return Ok(());
+                }
+                Some(Token::Turn(t)) if t != turn => {
+                    program.push(Motion::TurnV90(turn))?;
+                    tokens.consume(2);
+                }
+                _ => return Err(MotionError::Unrealizable(cell)),
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use super::direction::Direction;
+use super::error::ExplorerError;
+use super::explorer::*;
+use super::frontier::Frontier;
+use super::point::Point;
+use super::update_info::UpdateInfo;
+use super::wall::WallConflict;
+use arrayvec::ArrayVec;
+
+/// 機体から見た壁の観測結果
+/// 実機のセンサは前後左右で壁を返すので、向きと合わせて持っておく
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Observation {
+    /// 観測した区画
+    pub p: Point,
+    /// 機体の向き。上下左右のいずれか
+    pub heading: Direction,
+    /// 前方の壁。壁があればtrue、観測していなければNone
+    pub front: Option<bool>,
+    /// 左の壁
+    pub left: Option<bool>,
+    /// 右の壁
+    pub right: Option<bool>,
+    /// 後方の壁。センサがなければ普通はNone
+    pub back: Option<bool>,
+}
+impl Observation {
+    /// 壁をすべて未観測として生成します
+    pub fn new(p: Point, heading: Direction) -> Self {
+        Self {
+            p,
+            heading,
+            front: None,
+            left: None,
+            right: None,
+            back: None,
+        }
+    }
+
+    /// 絶対方向dirの壁の観測結果を返します
+    /// headingが上下左右以外、もしくはdirが上下左右以外ならNone
+    pub fn wall(&self, dir: Direction) -> Option<bool> {
+        if !Direction::ALL4.contains(&self.heading) || !Direction::ALL4.contains(&dir) {
+            return None;
+        }
+        match self.heading.turn_angle(dir)? {
+            0 => self.front,
+            90 => self.right,
+            -90 => self.left,
+            180 => self.back,
+            _ => None,
+        }
+    }
+
+    /// 上下左右の壁情報に変換します
+    /// headingが上下左右以外の場合はエラー
+    pub fn to_update_info(&self) -> Result<UpdateInfo, ExplorerError> {
+        if !Direction::ALL4.contains(&self.heading) {
+            return Err(ExplorerError::InvalidHeading(self.heading));
+        }
+        Ok(UpdateInfo {
+            p: self.p,
+            up: self.wall(Direction::Up),
+            down: self.wall(Direction::Down),
+            left: self.wall(Direction::Left),
+            right: self.wall(Direction::Right),
+        })
+    }
+}
+
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// 機体から見た壁情報で更新する
+    /// 変換できない向きの場合はエラー。それ以外はtry_updateと同じ
+    pub fn observe(
+        &mut self,
+        observation: &Observation,
+    ) -> Result<ArrayVec<WallConflict, 4>, ExplorerError> {
+        let info = observation.to_update_info()?;
+        self.try_update(&info)
+    }
+}
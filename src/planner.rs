@@ -0,0 +1,193 @@
+use super::answer::AnswerError;
+use super::direction::Direction;
+use super::explorer::*;
+use super::frontier::*;
+use super::point::Point;
+use arrayvec::ArrayVec;
+use core::convert::TryFrom;
+
+/// 向きを変えるときのコスト。角度は45度単位で、左右は区別しない
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct TurnCost {
+    pub turn45: usize,
+    pub turn90: usize,
+    pub turn135: usize,
+    pub turn180: usize,
+}
+impl TurnCost {
+    pub fn new(turn45: usize, turn90: usize, turn135: usize, turn180: usize) -> Self {
+        Self {
+            turn45,
+            turn90,
+            turn135,
+            turn180,
+        }
+    }
+
+    /// fromの向きからtoの向きに変えるコストを返します
+    /// fromがNoDirなら向きを問わないものとして0
+    pub fn get(&self, from: Direction, to: Direction) -> usize {
+        match from.turn_angle(to).map(i32::abs) {
+            Some(45) => self.turn45,
+            Some(90) => self.turn90,
+            Some(135) => self.turn135,
+            Some(180) => self.turn180,
+            _ => 0,
+        }
+    }
+}
+
+/// 向きを考慮して求めた経路
+#[derive(Clone, Debug)]
+pub struct HeadingPlan<const N: usize> {
+    /// 移動と旋回のコストの合計
+    pub cost: usize,
+    /// startからゴールまでの座標列
+    pub path: ArrayVec<Point, N>,
+}
+
+/// 向きの状態として使う添字。Direction::ALL8の並び
+fn heading_index(dir: Direction) -> Option<usize> {
+    Direction::ALL8.iter().position(|&d| d == dir)
+}
+
+/// 到達していない状態のコスト
+const PLAN_UNREACHABLE: u32 = u32::MAX;
+/// HeadingPlanWork::statesで、直前の向きを入れる下位bit
+const PLAN_FROM_MASK: u8 = 0x07;
+/// HeadingPlanWork::statesで、コストが確定した状態を示すbit
+const PLAN_SETTLED: u8 = 0x08;
+/// HeadingPlanWork::statesで、経路の始点になる状態を示すbit
+const PLAN_START: u8 = 0x10;
+
+/// plan_with_headingの作業領域
+/// (区画, 向き)ごとにコストを持つので32x32で約50KBになる。組込みではstaticに置いて使い回す
+pub struct HeadingPlanWork<const W: usize, const H: usize> {
+    /// 状態ごとのコスト。到達していなければPLAN_UNREACHABLE
+    pub costs: [[[u32; 8]; W]; H],
+    /// 状態ごとの、下位3bitがその状態に入る直前の向き、PLAN_SETTLEDが確定済、PLAN_STARTが始点
+    pub states: [[[u8; 8]; W]; H],
+    /// 未確定の状態を持つ区画。未確定のうち最小のコストを優先度にする
+    pub heap: SearchInfoHeap<W, H>,
+}
+impl<const W: usize, const H: usize> Default for HeadingPlanWork<W, H> {
+    fn default() -> Self {
+        Self {
+            costs: [[[PLAN_UNREACHABLE; 8]; W]; H],
+            states: [[[0; 8]; W]; H],
+            heap: SearchInfoHeap::default(),
+        }
+    }
+}
+impl<const W: usize, const H: usize> HeadingPlanWork<W, H> {
+    /// 前回の計画を消します
+    fn clear(&mut self) {
+        self.costs = [[[PLAN_UNREACHABLE; 8]; W]; H];
+        self.states = [[[0; 8]; W]; H];
+        self.heap.clear();
+    }
+
+    /// pの未確定の状態のうち、コストが最小の向き
+    fn min_heading(&self, p: Point) -> Option<usize> {
+        (0..Direction::ALL8.len())
+            .filter(|&h| self.states[p.y][p.x][h] & PLAN_SETTLED == 0)
+            .filter(|&h| self.costs[p.y][p.x][h] != PLAN_UNREACHABLE)
+            .min_by_key(|&h| self.costs[p.y][p.x][h])
+    }
+
+    /// pに未確定の状態が残っていれば、その最小コストで積み直す
+    fn push_min(&mut self, p: Point) {
+        if let Some(h) = self.min_heading(p) {
+            let cost = self.costs[p.y][p.x][h];
+            self.heap
+                .push(p, usize::try_from(cost).unwrap_or(usize::MAX));
+        }
+    }
+
+    /// 状態(p, h)のコストを、直前の向きfrom_hから来たcostで更新します
+    /// 確定済の状態や、u32に収まらないコストは無視する
+    fn relax(&mut self, p: Point, h: usize, cost: usize, from_h: usize) {
+        if self.states[p.y][p.x][h] & PLAN_SETTLED != 0 {
+            return;
+        }
+        let cost = match u32::try_from(cost) {
+            Ok(cost) if cost < self.costs[p.y][p.x][h] => cost,
+            _ => return,
+        };
+        self.costs[p.y][p.x][h] = cost;
+        // from_hはALL8の添字なので3bitに収まる
+        self.states[p.y][p.x][h] = from_h as u8 & PLAN_FROM_MASK;
+        self.push_min(p);
+    }
+}
+
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// (区画, 向き)を状態として、移動と旋回のコストの合計が最小になる経路を求めます
+    /// 既知の壁情報のみを使い、開始時の向きはstart_heading
+    /// コストの小さい状態から確定させる(ダイクストラ法)。作業領域workは前回の内容を消して使う
+    pub fn plan_with_heading<const N: usize>(
+        &self,
+        turn_cost: &TurnCost,
+        work: &mut HeadingPlanWork<W, H>,
+    ) -> Result<HeadingPlan<N>, AnswerError> {
+        // 開始位置がゴールなら動かない
+        let start = self.start;
+        if self.goal.contains(start) {
+            return Ok(HeadingPlan {
+                cost: 0,
+                path: core::iter::once(start).collect(),
+            });
+        }
+        work.clear();
+
+        // 開始時の向きを始点の状態にする。NoDirなら全ての向きから始められる
+        let start_headings = match heading_index(self.start_heading) {
+            Some(h) => h..h + 1,
+            None => 0..Direction::ALL8.len(),
+        };
+        for h in start_headings {
+            work.costs[start.y][start.x][h] = 0;
+            work.states[start.y][start.x][h] = PLAN_START;
+        }
+        work.push_min(start);
+
+        // 最初に確定したゴールの状態が最小コスト
+        let (mut p, mut h) = loop {
+            let p = work.heap.pop().ok_or(AnswerError::GoalNotReached)?;
+            let h = match work.min_heading(p) {
+                Some(h) => h,
+                None => continue,
+            };
+            work.states[p.y][p.x][h] |= PLAN_SETTLED;
+            if self.goal.contains(p) {
+                break (p, h);
+            }
+            let cost = usize::try_from(work.costs[p.y][p.x][h]).unwrap_or(usize::MAX);
+            for q in self.get_passable_arounds(p) {
+                let dir = p.direction_to(q);
+                let next_h = heading_index(dir).unwrap_or_default();
+                let next_cost = cost
+                    .saturating_add(self.step_cost.get(p, q))
+                    .saturating_add(turn_cost.get(Direction::ALL8[h], dir));
+                work.relax(q, next_h, next_cost, h);
+            }
+            // 残りの向きも後で確定させる
+            work.push_min(p);
+        };
+        let cost = usize::try_from(work.costs[p.y][p.x][h]).unwrap_or(usize::MAX);
+
+        // 入ってきた向きを逆に辿って始点の状態まで戻る。途中でstartを通り直すこともある
+        let mut path = ArrayVec::<Point, N>::new();
+        path.try_push(p)
+            .map_err(|_| AnswerError::CapacityExceeded)?;
+        while work.states[p.y][p.x][h] & PLAN_START == 0 {
+            let prev_h = usize::from(work.states[p.y][p.x][h] & PLAN_FROM_MASK);
+            p = p.get_around(Direction::ALL8[h].opposite());
+            h = prev_h;
+            path.try_push(p)
+                .map_err(|_| AnswerError::CapacityExceeded)?;
+        }
+        path.reverse();
+        Ok(HeadingPlan { cost, path })
+    }
+}
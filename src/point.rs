@@ -1,21 +1,16 @@
 use super::direction::Direction;
 
 /// 座標
-#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
 pub struct Point {
     pub x: usize,
     pub y: usize,
 }
-impl Default for Point {
-    fn default() -> Self {
-        Self { x: 0, y: 0 }
-    }
-}
 
 impl Point {
     /// ビルドオプション指定がなければ、チェビシフ距離を返します
     pub fn distance(&self, other: Point) -> usize {
-        use std::cmp;
+        use core::cmp;
 
         let dx = cmp::max(self.x, other.x) - cmp::min(self.x, other.x);
         let dy = cmp::max(self.y, other.y) - cmp::min(self.y, other.y);
@@ -26,10 +21,39 @@ impl Point {
             cmp::max(dx, dy)
         }
     }
+    /// toへの方向を返します。隣接していなければNoDir
+    pub fn direction_to(&self, to: Point) -> Direction {
+        Direction::from_delta(
+            to.x as isize - self.x as isize,
+            to.y as isize - self.y as isize,
+        )
+    }
+    /// 迷路サイズW x Hの中で、指定した方向に隣接する座標を返します
+    /// 迷路外に出る場合はNone
+    pub fn neighbor<const W: usize, const H: usize>(&self, dir: Direction) -> Option<Point> {
+        let (dx, dy) = dir.delta();
+        let x = self.x.checked_add_signed(dx)?;
+        let y = self.y.checked_add_signed(dy)?;
+        if self.x < W && self.y < H && x < W && y < H {
+            Some(Point { x, y })
+        } else {
+            None
+        }
+    }
+    /// 迷路サイズW x Hの中で隣接する座標を、方向とあわせて列挙します
+    /// 並びは上、右、下、左、(is_diagonalなら)左上、右上、左下、右下の順
+    pub fn neighbors<const W: usize, const H: usize>(&self, is_diagonal: bool) -> Neighbors<W, H> {
+        Neighbors {
+            p: *self,
+            index: 0,
+            count: if is_diagonal { 8 } else { 4 },
+        }
+    }
     /// 指定した方向にある座標を取得します。例外処理は内包していません
+    /// 迷路外に出る可能性がある場合はneighborを使う
     pub fn get_around(&self, dir: Direction) -> Point {
         match dir {
-            Direction::NoDir => self.clone(),
+            Direction::NoDir => *self,
             Direction::Up => Point {
                 x: self.x,
                 y: self.y + 1,
@@ -65,3 +89,34 @@ impl Point {
         }
     }
 }
+
+/// Point::neighborsで使うイテレータ
+pub struct Neighbors<const W: usize, const H: usize> {
+    p: Point,
+    index: usize,
+    count: usize,
+}
+impl<const W: usize, const H: usize> Iterator for Neighbors<W, H> {
+    type Item = (Direction, Point);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        const DIRECTIONS: [Direction; 8] = [
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+            Direction::UpLeft,
+            Direction::UpRight,
+            Direction::DownLeft,
+            Direction::DownRight,
+        ];
+        while self.index < self.count {
+            let dir = DIRECTIONS[self.index];
+            self.index += 1;
+            if let Some(p) = self.p.neighbor::<W, H>(dir) {
+                return Some((dir, p));
+            }
+        }
+        None
+    }
+}
@@ -1,6 +1,22 @@
+pub use super::answer::*;
 pub use super::cell::*;
 pub use super::direction::*;
+pub use super::error::*;
+pub use super::estimator::*;
 pub use super::explorer::*;
+pub use super::frontier::*;
+pub use super::generator::*;
+pub use super::goal::*;
+pub use super::maz::*;
+pub use super::maze::*;
+pub use super::mazefile::*;
+pub use super::motion::*;
+pub use super::observation::*;
+pub use super::planner::*;
 pub use super::point::*;
 pub use super::search_info::*;
+pub use super::simulator::*;
+pub use super::step_cost::*;
+pub use super::step_map::*;
 pub use super::update_info::*;
+pub use super::wall::*;
@@ -1,9 +1,11 @@
 use super::cell::*;
 use super::direction::Direction;
+use super::error::ExplorerError;
 use super::explorer::*;
+use super::frontier::Frontier;
 use super::point::Point;
-
-pub const SEARCH_INFO_STORE_SIZE: usize = MAZE_WIDTH * MAZE_HEIGHT; // 暫定値、組み込みはSRAMが貧相だぞ
+use super::wall::WallState;
+use arrayvec::ArrayVec;
 
 /// 普段はARMなのでx,y等すべてu32で扱いたいが、サイズがでかくなるのでここだけ圧縮する
 #[derive(Copy, Clone, Debug)]
@@ -37,255 +39,444 @@ impl SearchInfo {
     }
 }
 
-/// Stackにして深さ優先、追加履歴が可能な限り近いところから取り出す
-pub struct SearchInfoProvider {
-    pub datas: [SearchInfo; SEARCH_INFO_STORE_SIZE],
-    pub wr_ptr: usize,
-}
-impl Default for SearchInfoProvider {
-    fn default() -> Self {
-        Self {
-            datas: [SearchInfo::default(); SEARCH_INFO_STORE_SIZE],
-            wr_ptr: 0,
+/// 探索状態を破棄するときに残すフラグ。壁情報と観測済かどうか
+const KEEP_ON_RESET: CellFlag = CellFlag::from_bits_truncate(
+    CellFlag::IS_UPDATED.bits()
+        | CellFlag::IS_EXISTS_RIGHT_WALL.bits()
+        | CellFlag::IS_UPDATED_RIGHT_WALL.bits()
+        | CellFlag::IS_EXISTS_UP_WALL.bits()
+        | CellFlag::IS_UPDATED_UP_WALL.bits(),
+);
+
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// 壁情報を残して探索状態を破棄し、startから探索し直せるようにします
+    pub fn reset_search(&mut self) {
+        for row in self.cells.iter_mut() {
+            for c in row.iter_mut() {
+                c.cost = usize::MAX;
+                c.from_info = SearchInfo::default();
+                c.flag &= KEEP_ON_RESET;
+            }
         }
+        self.provider.clear();
+        self.min_cost = None;
+
+        // 有効コスト設定と最初の検索対象に追加
+        let start = self.start;
+        self.cells[start.y][start.x].cost = 0;
+        self.cells[start.y][start.x]
+            .flag
+            .insert(CellFlag::IS_COST_AVAILABLE | CellFlag::IS_PROVIDER_PUSHED);
+        let cost = self.goal.estimate(start, &self.step_cost);
+        self.provider.push(start, cost);
     }
-}
-impl SearchInfoProvider {
-    pub fn get_count(&self) -> usize {
-        self.wr_ptr
-    }
-    pub fn get_free(&self) -> usize {
-        SEARCH_INFO_STORE_SIZE - self.wr_ptr
-    }
-    pub fn clear(&mut self) {
-        self.wr_ptr = 0;
+
+    /// 壁の状態が変わった後に、使えなくなったコストだけを捨てて探索を続けられるようにします
+    /// from_infoを辿って通れなくなった移動があれば、その先の区画のコストと探索状態を捨てる
+    /// 残った区画から展開し直し、最小コストと読み捨てた区画も今の状態に合わせる
+    /// 積み直しに失敗した場合も最後まで行い、エラーを返す
+    pub fn invalidate_search(&mut self) -> Result<(), ExplorerError> {
+        self.clear_answer();
+        for y in 0..H {
+            for x in 0..W {
+                self.invalidate_chain(Point { x, y });
+            }
+        }
+        for row in self.cells.iter_mut() {
+            for c in row.iter_mut() {
+                c.flag.remove(CellFlag::IS_COST_CHECKED);
+            }
+        }
+
+        // ゴール区画のコストを捨てた場合は、最小コストも残った区画から求め直す
+        self.min_cost = self
+            .goal
+            .cells()
+            .filter(|g| g.x < W && g.y < H)
+            .filter(|g| {
+                self.cells[g.y][g.x]
+                    .flag
+                    .contains(CellFlag::IS_COST_AVAILABLE)
+            })
+            .map(|g| self.cells[g.y][g.x].cost)
+            .min();
+
+        // 展開済で、隣接区画のコストと食い違っている区画を展開し直す
+        // 壁は観測済なので、その場に行かなくても展開できる
+        let mut result = Ok(());
+        for y in 0..H {
+            for x in 0..W {
+                let p = Point { x, y };
+                let flag = self.cells[y][x].flag;
+                if flag.contains(CellFlag::IS_SEARCH_AROUND | CellFlag::IS_COST_AVAILABLE)
+                    && !self.is_consistent(p)
+                {
+                    if let Err(e) = self.try_fetch_targets(p) {
+                        result = Err(e);
+                    }
+                }
+            }
+        }
+
+        // 最短を改善できないとして読み捨てた区画も、最小コストが変わっていれば積み直す
+        for y in 0..H {
+            for x in 0..W {
+                let p = Point { x, y };
+                if !self.cells[y][x]
+                    .flag
+                    .contains(CellFlag::IS_INVALIDATED | CellFlag::IS_COST_AVAILABLE)
+                {
+                    continue;
+                }
+                if let Some(cost) = self.reserve_target(p) {
+                    if let Err(e) = self.provider.try_push(p, cost) {
+                        result = Err(e);
+                    }
+                }
+            }
+        }
+        result
     }
-    pub fn push(&mut self, p: Point) -> bool {
-        if self.wr_ptr < (SEARCH_INFO_STORE_SIZE - 1) {
-            self.datas[self.wr_ptr] = SearchInfo::from(p);
-            self.wr_ptr += 1;
-            true
-        } else {
-            // 無理だけど普通にfalse返すだけで良さげ
-            debug_assert!(false);
-            false
+
+    /// pからfrom_infoを辿り、通れなくなった移動があればその先の区画のコストを捨てます
+    /// 通れることを確認した区画にはIS_COST_CHECKEDを立て、以降はそこで打ち切る
+    fn invalidate_chain(&mut self, p: Point) {
+        // 1回目で判定して、反映する区画数を数える
+        let mut is_stale = false;
+        let mut count = 0;
+        let mut q = p;
+        while count < W * H {
+            let c = &self.cells[q.y][q.x];
+            if q == self.start
+                || c.flag.contains(CellFlag::IS_COST_CHECKED)
+                || !c.flag.contains(CellFlag::IS_COST_AVAILABLE)
+            {
+                // コストを捨てた区画から来ていれば、ここまでも捨てる
+                is_stale = q != self.start && !c.flag.contains(CellFlag::IS_COST_CHECKED);
+                break;
+            }
+            count += 1;
+            let parent = c.from_info.to_point();
+            if parent.x >= W || parent.y >= H || !self.get_passable_arounds(parent).contains(&q) {
+                is_stale = true;
+                break;
+            }
+            q = parent;
+        }
+
+        // 2回目で反映する
+        let mut q = p;
+        for _ in 0..count {
+            let c = &mut self.cells[q.y][q.x];
+            let parent = c.from_info.to_point();
+            if is_stale {
+                c.cost = usize::MAX;
+                c.from_info = SearchInfo::default();
+                c.flag &= KEEP_ON_RESET;
+            } else {
+                c.flag.insert(CellFlag::IS_COST_CHECKED);
+            }
+            q = parent;
         }
     }
-    pub fn pop(&mut self) -> Option<Point> {
-        if self.wr_ptr > 0 {
-            self.wr_ptr -= 1; // read有効データは書き込み先のひとつ下
-            let data = self.datas[self.wr_ptr];
-            Some(data.to_point())
-        } else {
-            None
+
+    /// pの周りの壁がなくなった後に、新しく通れるようになった移動を探索に反映させます
+    /// 壁1枚で移動可否が変わるのは斜めの迂回ルートを含めて距離2以内の区画なので、
+    /// そのうち展開済で、隣接区画のコストと食い違っている区画を展開し直す
+    pub fn expand_opened(&mut self, p: Point) -> Result<(), ExplorerError> {
+        let mut result = Ok(());
+        for y in p.y.saturating_sub(2)..(p.y + 3).min(H) {
+            for x in p.x.saturating_sub(2)..(p.x + 3).min(W) {
+                let q = Point { x, y };
+                let flag = self.cells[y][x].flag;
+                if flag.contains(CellFlag::IS_SEARCH_AROUND | CellFlag::IS_COST_AVAILABLE)
+                    && !self.is_consistent(q)
+                {
+                    if let Err(e) = self.try_fetch_targets(q) {
+                        result = Err(e);
+                    }
+                }
+            }
         }
+        result
     }
-}
-impl Explorer {
+
+    /// pから移動できる区画のコストが、pを経由したコスト以下になっていればtrue
+    fn is_consistent(&self, p: Point) -> bool {
+        let cost = self.cells[p.y][p.x].cost;
+        self.get_passable_arounds(p).into_iter().all(|q| {
+            let c = &self.cells[q.y][q.x];
+            c.flag.contains(CellFlag::IS_COST_AVAILABLE)
+                && c.cost <= cost + self.step_cost.get(p, q)
+        })
+    }
+
     /// 次に進むべき座標を取得します
+    /// 予約済の区画のうち、f = (ここまでのコスト) + (ゴールまでの推定コスト)が最小のものを返す
+    /// 推定コストはstep_costによるオクタイル距離で実際のコストを超えないので、
+    /// 壁情報をupdateで反映していれば、Noneが返った時点のmin_costは既知の壁情報における最短になる
     pub fn get_next(&mut self) -> Option<Point> {
-        self.provider.pop()
+        while let Some(p) = self.provider.pop() {
+            // 積み直しで重複しているものや、展開済のもの、壁が変わってコストを捨てたものは読み捨てる
+            let flag = self.cells[p.y][p.x].flag;
+            if flag.contains(CellFlag::IS_SEARCH_AROUND)
+                || !flag.contains(CellFlag::IS_COST_AVAILABLE)
+            {
+                continue;
+            }
+            // 積んだ後にゴールが見つかって、最短を改善できなくなったものも読み捨てる
+            let cost = self.cells[p.y][p.x].cost + self.goal.estimate(p, &self.step_cost);
+            if let Some(min_cost) = self.min_cost {
+                if min_cost < cost {
+                    self.cells[p.y][p.x].flag.insert(CellFlag::IS_INVALIDATED);
+                    continue;
+                }
+            }
+            return Some(p);
+        }
+        None
     }
 
     /// 周辺セルを探索対象として追加します
-    /// 追加する際に優先度が高い順になるようにすることでa*もどきっぽく振る舞います
+    /// 失敗した場合は何もしない。原因を知りたい場合はtry_fetch_targetsを使う
     pub fn fetch_targets(&mut self, p: Point) {
-        debug_assert!(self.cells[p.y][p.x]
+        let result = self.try_fetch_targets(p);
+        debug_assert!(result.is_ok(), "{:?}", result);
+    }
+
+    /// 周辺セルを探索対象として追加します
+    /// 優先度には最短予測値を使い、取り出し順をa*として振る舞います
+    /// 迷路外やコスト未確定の区画、探索対象を積みきれない場合はエラー
+    pub fn try_fetch_targets(&mut self, p: Point) -> Result<(), ExplorerError> {
+        if p.x >= W || p.y >= H {
+            return Err(ExplorerError::OutOfBounds(p));
+        }
+        if !self.cells[p.y][p.x]
             .flag
-            .contains(CellFlag::IS_COST_AVAILABLE));
-        let current_cost = self.cells[p.y][p.x].cost + 1;
+            .contains(CellFlag::IS_COST_AVAILABLE)
+        {
+            return Err(ExplorerError::CostUnavailable(p));
+        }
+        let current_cost = self.cells[p.y][p.x].cost;
 
         // 座標, cost_total
         // costとcost_totalを更新してソートして追加する
-        use arrayvec::ArrayVec;
         const TARGET_NUM: usize = 8; // 8方位
-        let mut targets = ArrayVec::<[(Point, Option<usize>); TARGET_NUM]>::new();
-
-        // 探索Stackに余裕がなければ諦める
-        if TARGET_NUM > self.provider.get_free() {
-            return;
+        let mut targets = ArrayVec::<(Point, Option<usize>), TARGET_NUM>::new();
+        for target_point in self.get_passable_arounds(p) {
+            targets.push((target_point, None));
         }
 
-        // 上下左右の区画に移動可能かを判定する
-        let is_passing_up = p.y < MAZE_HEIGHT - 1
-            && self.cells[p.y][p.x]
-                .flag
-                .contains(CellFlag::IS_UPDATED_UP_WALL)
-            && !self.cells[p.y][p.x]
-                .flag
-                .contains(CellFlag::IS_EXISTS_UP_WALL);
-        let is_passing_right = p.x < MAZE_WIDTH - 1
-            && self.cells[p.y][p.x]
-                .flag
-                .contains(CellFlag::IS_UPDATED_RIGHT_WALL)
-            && !self.cells[p.y][p.x]
-                .flag
-                .contains(CellFlag::IS_EXISTS_RIGHT_WALL);
-
-        let is_passing_down = p.y > 0
-            && self.cells[p.y - 1][p.x]
-                .flag
-                .contains(CellFlag::IS_UPDATED_UP_WALL)
-            && !self.cells[p.y - 1][p.x]
-                .flag
-                .contains(CellFlag::IS_EXISTS_UP_WALL);
-        let is_passing_left = p.x > 0
-            && self.cells[p.y][p.x - 1]
-                .flag
-                .contains(CellFlag::IS_UPDATED_RIGHT_WALL)
-            && !self.cells[p.y][p.x - 1]
-                .flag
-                .contains(CellFlag::IS_EXISTS_RIGHT_WALL);
+        // 探索Stackに余裕がなければ、何も変更せずに諦める
+        // 数えるのは実際に積む区画だけ。格納済の区画を付け替えられる戦略では容量を消費しない
+        let required = targets
+            .iter()
+            .filter(|(target_point, _)| {
+                let target = &self.cells[target_point.y][target_point.x];
+                let next_cost = current_cost + self.step_cost.get(p, *target_point);
+                let is_improved =
+                    target.flag.contains(CellFlag::IS_COST_AVAILABLE) && next_cost < target.cost;
+                !target.flag.contains(CellFlag::IS_SEARCH_AROUND)
+                    && (is_improved || !target.flag.contains(CellFlag::IS_PROVIDER_PUSHED))
+                    && !self.provider.contains(*target_point)
+            })
+            .count();
+        if required > self.provider.get_free() {
+            return Err(ExplorerError::FrontierFull);
+        }
 
-        // 斜め方向の区画に移動可能か判定する。斜め走行前提
-        // 迂回ルートは2種類あるので、どちらかを満たしていればよい
-        let is_passing_up_left = (p.x > 0)
-            && ((is_passing_up
-                && self.cells[p.y + 1][p.x - 1]
-                    .flag
-                    .contains(CellFlag::IS_UPDATED_RIGHT_WALL)
-                && !self.cells[p.y + 1][p.x - 1]
-                    .flag
-                    .contains(CellFlag::IS_EXISTS_RIGHT_WALL))
-                || (is_passing_left
-                    && self.cells[p.y][p.x - 1]
-                        .flag
-                        .contains(CellFlag::IS_UPDATED_UP_WALL)
-                    && !self.cells[p.y][p.x - 1]
-                        .flag
-                        .contains(CellFlag::IS_EXISTS_UP_WALL)));
+        // Cellの情報に埋め込む
+        let info = SearchInfo::from(p);
+        let mut is_dirty = false;
 
-        let is_passing_up_right = (is_passing_up
-            && self.cells[p.y + 1][p.x]
-                .flag
-                .contains(CellFlag::IS_UPDATED_RIGHT_WALL)
-            && !self.cells[p.y + 1][p.x]
+        for (target_point, target_cost) in &mut targets {
+            // コスト更新。斜めと上下左右で移動コストが異なる
+            let next_cost = current_cost + self.step_cost.get(p, *target_point);
+            let is_updated =
+                self.cells[target_point.y][target_point.x].update_cost(next_cost, info);
+            is_dirty |= self.cells[target_point.y][target_point.x]
                 .flag
-                .contains(CellFlag::IS_EXISTS_RIGHT_WALL))
-            || (is_passing_right
-                && self.cells[p.y][p.x + 1]
-                    .flag
-                    .contains(CellFlag::IS_UPDATED_UP_WALL)
-                && !self.cells[p.y][p.x + 1]
-                    .flag
-                    .contains(CellFlag::IS_EXISTS_UP_WALL));
-
-        let is_passing_down_left = (p.x > 0 && p.y > 0)
-            && ((is_passing_down
-                && self.cells[p.y - 1][p.x - 1]
-                    .flag
-                    .contains(CellFlag::IS_UPDATED_RIGHT_WALL)
-                && !self.cells[p.y - 1][p.x - 1]
-                    .flag
-                    .contains(CellFlag::IS_EXISTS_RIGHT_WALL))
-                || (is_passing_left
-                    && self.cells[p.y - 1][p.x - 1]
-                        .flag
-                        .contains(CellFlag::IS_UPDATED_UP_WALL)
-                    && !self.cells[p.y - 1][p.x - 1]
-                        .flag
-                        .contains(CellFlag::IS_EXISTS_UP_WALL)));
+                .contains(CellFlag::IS_COST_DIRTY);
 
-        let is_passing_down_right = (p.y > 0)
-            && ((is_passing_down
-                && self.cells[p.y - 1][p.x]
+            // 検索予約に追加。予約済でもコストが下がっていれば積み直す
+            if is_updated
+                || !self.cells[target_point.y][target_point.x]
                     .flag
-                    .contains(CellFlag::IS_UPDATED_RIGHT_WALL)
-                && !self.cells[p.y - 1][p.x]
-                    .flag
-                    .contains(CellFlag::IS_EXISTS_RIGHT_WALL))
-                || (is_passing_right
-                    && self.cells[p.y - 1][p.x + 1]
-                        .flag
-                        .contains(CellFlag::IS_UPDATED_UP_WALL)
-                    && !self.cells[p.y - 1][p.x + 1]
-                        .flag
-                        .contains(CellFlag::IS_EXISTS_UP_WALL)));
-
-        if is_passing_up {
-            targets.push((p.get_around(Direction::Up), None));
-        }
-        if is_passing_right {
-            targets.push((p.get_around(Direction::Right), None));
-        }
-        if is_passing_down {
-            targets.push((p.get_around(Direction::Down), None));
+                    .contains(CellFlag::IS_PROVIDER_PUSHED)
+            {
+                *target_cost = self.reserve_target(*target_point);
+            }
         }
-        if is_passing_left {
-            targets.push((p.get_around(Direction::Left), None));
+
+        // コストの大きい順に追加する。同じコストは列挙順の後ろから
+        // 安定ソートはallocが必要なので、列挙順も含めたキーでソートする
+        let mut orders = ArrayVec::<(usize, usize), TARGET_NUM>::new();
+        for (index, (_point, target_cost)) in targets.iter().enumerate() {
+            if let Some(cost) = target_cost {
+                orders.push((*cost, index));
+            }
         }
-        if is_passing_up_left {
-            targets.push((p.get_around(Direction::UpLeft), None));
+        orders.sort_unstable();
+        for &(cost, index) in orders.iter().rev() {
+            self.provider.try_push(targets[index].0, cost)?;
         }
-        if is_passing_up_right {
-            targets.push((p.get_around(Direction::UpRight), None));
+
+        // 周辺探索完了フラグ
+        self.cells[p.y][p.x].flag.insert(CellFlag::IS_SEARCH_AROUND);
+
+        // 既存ルートより短いルートが見つかっていれば、展開済の区画にも反映させる
+        if is_dirty {
+            self.relax_costs()?;
         }
-        if is_passing_down_left {
-            targets.push((p.get_around(Direction::DownLeft), None));
+        Ok(())
+    }
+
+    /// 検索予約の状態を更新して、積むべきであれば優先度を返します
+    /// 展開済の区画や、既存の最短コストを理想値時点で改善できない区画はNone
+    fn reserve_target(&mut self, p: Point) -> Option<usize> {
+        if self.cells[p.y][p.x]
+            .flag
+            .contains(CellFlag::IS_SEARCH_AROUND)
+        {
+            return None;
         }
-        if is_passing_down_right {
-            targets.push((p.get_around(Direction::DownRight), None));
+        // 検索履歴に残す
+        self.cells[p.y][p.x]
+            .flag
+            .insert(CellFlag::IS_PROVIDER_PUSHED);
+        // 原位置からの最短予測値を算出
+        let cost = self.cells[p.y][p.x].cost + self.goal.estimate(p, &self.step_cost);
+        // 既存の最短コストを理想値時点で改善できない場所は省略する
+        if let Some(min_cost) = self.min_cost {
+            if min_cost < cost {
+                self.cells[p.y][p.x].flag.insert(CellFlag::IS_INVALIDATED);
+                return None;
+            }
         }
+        self.cells[p.y][p.x].flag.remove(CellFlag::IS_INVALIDATED);
 
-        // Cellの情報に埋め込む
-        let info = SearchInfo::from(p);
-
-        for (target_point, target_cost) in &mut targets {
-            // コスト更新
-            self.cells[target_point.y][target_point.x].update_cost(current_cost, info);
-            // TODO: cost dirtyの解消
+        // ゴールだった場合は状態更新
+        if self.goal.contains(p) {
+            debug_assert!(self.goal.estimate(p, &self.step_cost) == 0);
+            self.min_cost = Some(cost);
+        }
+        // Queue追加時の優先度判断用に、最短予測値を使う(A*参考)
+        Some(cost)
+    }
 
-            // 検索予約に追加
-            if !self.cells[target_point.y][target_point.x]
-                .flag
-                .contains(CellFlag::IS_SEARCH_AROUND)
-                && !self.cells[target_point.y][target_point.x]
-                    .flag
-                    .contains(CellFlag::IS_PROVIDER_PUSHED)
-            {
-                // 検索履歴に残す
-                self.cells[target_point.y][target_point.x]
-                    .flag
-                    .insert(CellFlag::IS_PROVIDER_PUSHED);
-                // 原位置からの最短予測値を算出
-                let cost = self.cells[target_point.y][target_point.x].cost
-                    + self.goal.distance(*target_point);
-                // 既存の最短コストを理想値時点で改善できない場所は省略する
-                if let Some(min_cost) = self.min_cost {
-                    if min_cost < cost {
-                        self.cells[target_point.y][target_point.x]
-                            .flag
-                            .insert(CellFlag::IS_INVALIDATED);
+    /// IS_COST_DIRTYの区画から、改善したコストを周辺区画に伝搬させます
+    /// 伝搬先のコストが下がればそこもDIRTYになるので、DIRTYがなくなるまで繰り返す
+    /// 完了後は、展開済の区画を経由して下がるコストがすべて反映されている
+    /// 積み直しに失敗した場合も伝搬は最後まで行い、エラーを返す
+    pub fn relax_costs(&mut self) -> Result<(), ExplorerError> {
+        let mut result = Ok(());
+        let mut is_changed = true;
+        while is_changed {
+            is_changed = false;
+            for y in 0..H {
+                for x in 0..W {
+                    if !self.cells[y][x].flag.contains(CellFlag::IS_COST_DIRTY) {
                         continue;
                     }
-                }
-                // Queue追加時の優先度判断用に、最短予測値を使う(A*参考)
-                *target_cost = Some(cost);
+                    self.cells[y][x].flag.remove(CellFlag::IS_COST_DIRTY);
 
-                // ゴールだった場合は状態更新
-                if *target_point == self.goal {
-                    debug_assert!(self.goal.distance(*target_point) == 0);
-                    self.min_cost = *target_cost;
+                    let p = Point { x, y };
+                    let info = SearchInfo::from(p);
+                    for target_point in self.get_passable_arounds(p) {
+                        let next_cost = self.cells[y][x].cost + self.step_cost.get(p, target_point);
+                        let target = &mut self.cells[target_point.y][target_point.x];
+                        // まだコストがついていない区画は、通常の探索で展開される
+                        if target.flag.contains(CellFlag::IS_COST_AVAILABLE)
+                            && next_cost < target.cost
+                        {
+                            target.update_cost(next_cost, info);
+                            is_changed = true;
+                            // 予約済で未展開の区画は新しい優先度で積み直す
+                            if let Some(cost) = self.reserve_target(target_point) {
+                                if let Err(e) = self.provider.try_push(target_point, cost) {
+                                    result = Err(e);
+                                }
+                            }
+                        }
+                    }
                 }
             }
         }
-
-        // コストの大きい順に追加する
-        targets.sort_by_key(|&(_point, cost)| {
-            if let Some(c) = cost {
-                c
-            } else {
-                std::usize::MAX
+        // ゴール区画のコストが下がっていれば最小コストも追従させる
+        if self.min_cost.is_some() {
+            for g in self.goal.cells() {
+                if g.x < W
+                    && g.y < H
+                    && self.cells[g.y][g.x]
+                        .flag
+                        .contains(CellFlag::IS_COST_AVAILABLE)
+                    && Some(self.cells[g.y][g.x].cost) < self.min_cost
+                {
+                    self.min_cost = Some(self.cells[g.y][g.x].cost);
+                }
             }
-        });
-        targets.reverse();
-        for (target_point, target_cost) in &targets {
-            if let Some(_) = target_cost {
-                self.provider.push(*target_point);
+        }
+        result
+    }
+
+    /// 壁情報が確定していて、移動可能な周辺区画を列挙します
+    /// 並びは上、右、下、左、左上、右上、左下、右下の順
+    pub fn get_passable_arounds(&self, p: Point) -> ArrayVec<Point, 8> {
+        self.get_arounds(p, false, true)
+    }
+
+    /// 移動可能な周辺区画を列挙します
+    /// is_unknown_openなら壁情報が未確定の壁は存在しないものとして扱う
+    /// is_diagonalなら斜め方向も含める。斜め走行前提で、迂回ルート2種のどちらかを満たしていればよい
+    pub fn get_arounds(
+        &self,
+        p: Point,
+        is_unknown_open: bool,
+        is_diagonal: bool,
+    ) -> ArrayVec<Point, 8> {
+        let mut dst = ArrayVec::new();
+        let is_open = |p: Point, dir: Direction| self.is_open(p, dir, is_unknown_open);
+
+        // 上下左右の区画に移動可能かを判定する
+        // 斜め方向は迂回ルート2種のどちらかが通れれば移動可能
+        let is_open_via = |first: Direction, second: Direction| {
+            is_open(p, first)
+                && p.neighbor::<W, H>(first)
+                    .is_some_and(|q| is_open(q, second))
+        };
+        for (dir, target_point) in p.neighbors::<W, H>(is_diagonal) {
+            let is_passable = match dir {
+                Direction::UpLeft => {
+                    is_open_via(Direction::Up, Direction::Left)
+                        || is_open_via(Direction::Left, Direction::Up)
+                }
+                Direction::UpRight => {
+                    is_open_via(Direction::Up, Direction::Right)
+                        || is_open_via(Direction::Right, Direction::Up)
+                }
+                Direction::DownLeft => {
+                    is_open_via(Direction::Down, Direction::Left)
+                        || is_open_via(Direction::Left, Direction::Down)
+                }
+                Direction::DownRight => {
+                    is_open_via(Direction::Down, Direction::Right)
+                        || is_open_via(Direction::Right, Direction::Down)
+                }
+                _ => is_open(p, dir),
+            };
+            if is_passable {
+                dst.push(target_point);
             }
         }
+        dst
+    }
 
-        // 周辺探索完了フラグ
-        self.cells[p.y][p.x].flag.insert(CellFlag::IS_SEARCH_AROUND);
+    /// pからdir方向(上下左右)の壁がなく、隣の区画に移動可能であればtrue
+    fn is_open(&self, p: Point, dir: Direction, is_unknown_open: bool) -> bool {
+        match self.wall(p, dir) {
+            WallState::Absent => true,
+            WallState::Unknown => is_unknown_open,
+            WallState::Present => false,
+        }
     }
 }
@@ -0,0 +1,124 @@
+use super::cell::*;
+use super::direction::Direction;
+use super::error::ExplorerError;
+use super::explorer::*;
+use super::frontier::*;
+use super::maze::Maze;
+use super::point::Point;
+use super::step_map::Connectivity;
+
+/// シミュレーション結果
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct SimulationReport {
+    /// get_nextで取得して移動した回数
+    pub steps: usize,
+    /// 移動距離の合計(区画数)
+    /// 確定済の壁で通れる上下左右の経路を歩数マップで辿り、通った区画を数える
+    pub distance: usize,
+    /// 初めてゴール区画に入った時点のsteps。到達していなければNone
+    pub goal_steps: Option<usize>,
+    /// 終了時点の最小コスト
+    pub min_cost: Option<usize>,
+}
+
+/// 正解の迷路を隠し持ち、仮想マウスを動かしてExplorerに探索させる
+pub struct Simulator<const W: usize, const H: usize, F: Frontier = SearchInfoHeap<W, H>> {
+    /// 正解の迷路
+    pub maze: Maze<W, H>,
+    /// 探索させるExplorer。迷路の開始位置、ゴールで初期化される
+    pub explorer: Explorer<W, H, F>,
+    /// 仮想マウスの現在位置
+    pub position: Point,
+    /// ここまでの結果
+    pub report: SimulationReport,
+}
+
+impl<const W: usize, const H: usize, F: Frontier + Default> Simulator<W, H, F> {
+    pub fn new(maze: Maze<W, H>) -> Self {
+        let explorer = Explorer::with_start(maze.goal.clone(), maze.start, Direction::Up);
+        Self::with_explorer(maze, explorer)
+    }
+
+    /// 開始位置や向きを設定済のExplorerを使う場合
+    pub fn with_explorer(maze: Maze<W, H>, explorer: Explorer<W, H, F>) -> Self {
+        Self {
+            position: explorer.start,
+            maze,
+            explorer,
+            report: SimulationReport::default(),
+        }
+    }
+
+    /// 次の目標区画へ移動し、壁を観測して周辺を探索対象に追加します
+    /// 探索対象がなくなっていればNone
+    pub fn step(&mut self) -> Result<Option<Point>, ExplorerError> {
+        let target = match self.explorer.get_next() {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+        self.report.steps += 1;
+        self.move_to(target)?;
+
+        // 移動していなくても、目標区画の壁は見えている
+        self.observe(target)?;
+        // 壁が変わってコストが無効になっていれば、積み直された探索で再びここに来る
+        if self.explorer.cells[target.y][target.x]
+            .flag
+            .contains(CellFlag::IS_COST_AVAILABLE)
+        {
+            self.explorer.try_fetch_targets(target)?;
+        }
+
+        if self.report.goal_steps.is_none() && self.explorer.goal.contains(target) {
+            self.report.goal_steps = Some(self.report.steps);
+        }
+        self.report.min_cost = self.explorer.min_cost;
+        Ok(Some(target))
+    }
+
+    /// 確定済の壁で通れる経路を辿ってtargetまで移動し、通った区画数を加算します
+    /// 途中で入った区画の壁も観測する。通れる経路がなければエラー
+    fn move_to(&mut self, target: Point) -> Result<(), ExplorerError> {
+        let step_map = self
+            .explorer
+            .compute_step_map_to(target, Connectivity::Four);
+        if step_map.get(self.position).is_none() {
+            return Err(ExplorerError::Unreachable(target));
+        }
+        while let Some(next) = self.explorer.get_step_next(&step_map, self.position) {
+            self.report.distance += 1;
+            self.position = next;
+            self.observe(next)?;
+        }
+        debug_assert_eq!(self.position, target);
+        Ok(())
+    }
+
+    /// 区画に入ったときに見える壁を、未観測であればExplorerに反映します
+    fn observe(&mut self, p: Point) -> Result<(), ExplorerError> {
+        if !self.explorer.cells[p.y][p.x]
+            .flag
+            .contains(CellFlag::IS_UPDATED)
+        {
+            self.explorer.try_update(&self.maze.get_update_info(p))?;
+        }
+        Ok(())
+    }
+
+    /// ゴール区画に入るか、探索対象がなくなるまで進めます
+    pub fn run(&mut self) -> Result<SimulationReport, ExplorerError> {
+        while self.report.goal_steps.is_none() {
+            if self.step()?.is_none() {
+                break;
+            }
+        }
+        Ok(self.report)
+    }
+
+    /// 探索対象がなくなるまで進めます
+    /// 終了時点のmin_costは観測済の壁情報における最短になる
+    pub fn run_to_end(&mut self) -> Result<SimulationReport, ExplorerError> {
+        while self.step()?.is_some() {}
+        Ok(self.report)
+    }
+}
@@ -0,0 +1,60 @@
+use super::point::Point;
+
+/// 1区画移動するときのコスト
+/// 物理的な距離に合わせるなら、直進10に対して斜め14のように指定する
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct StepCost {
+    /// 上下左右への移動
+    straight: usize,
+    /// 斜めへの移動。straight以上、straightの2倍以下
+    diagonal: usize,
+}
+impl Default for StepCost {
+    /// 直進も斜めも1
+    fn default() -> Self {
+        Self {
+            straight: 1,
+            diagonal: 1,
+        }
+    }
+}
+
+impl StepCost {
+    /// 上下左右、斜めの移動コストを指定して生成します
+    /// diagonalがstraight以上、straightの2倍以下でなければ推定値が使えないのでNone
+    pub fn new(straight: usize, diagonal: usize) -> Option<Self> {
+        if straight <= diagonal && diagonal <= straight.saturating_mul(2) {
+            Some(Self { straight, diagonal })
+        } else {
+            None
+        }
+    }
+
+    /// 上下左右への移動コスト
+    pub fn straight(&self) -> usize {
+        self.straight
+    }
+    /// 斜めへの移動コスト
+    pub fn diagonal(&self) -> usize {
+        self.diagonal
+    }
+
+    /// 隣接する区画fromからtoへの移動コストを返します
+    pub fn get(&self, from: Point, to: Point) -> usize {
+        if from.x != to.x && from.y != to.y {
+            self.diagonal
+        } else {
+            self.straight
+        }
+    }
+
+    /// 壁がないものとしたときの最小コスト(オクタイル距離)を返します
+    /// A*の推定値に使うので、実際のコストを超えない
+    pub fn distance(&self, a: Point, b: Point) -> usize {
+        let dx = a.x.max(b.x) - a.x.min(b.x);
+        let dy = a.y.max(b.y) - a.y.min(b.y);
+        let diagonal_steps = dx.min(dy);
+        let straight_steps = dx.max(dy) - diagonal_steps;
+        diagonal_steps * self.diagonal + straight_steps * self.straight
+    }
+}
@@ -0,0 +1,127 @@
+use super::explorer::*;
+use super::frontier::*;
+use super::point::Point;
+use core::convert::TryFrom;
+
+/// 歩数マップで到達できない区画の値
+pub const STEP_UNREACHABLE: u16 = u16::MAX;
+
+/// 歩数マップを作る際の隣接の取り方
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Connectivity {
+    /// 上下左右のみ
+    Four,
+    /// 斜めを含む8方向。fetch_targetsと同じくstep_costで数える
+    Eight,
+}
+
+/// 足立法で使う歩数マップ
+/// 各区画からゴール区画までの歩数を持つ。Eightの場合はstep_costによるコスト
+#[derive(Copy, Clone, Debug)]
+pub struct StepMap<const W: usize, const H: usize> {
+    /// 歩数。到達できない、もしくはu16に収まらない区画はSTEP_UNREACHABLE
+    pub steps: [[u16; W]; H],
+    /// 作成時の隣接の取り方
+    pub connectivity: Connectivity,
+    /// 作成時に未確定の壁を存在しないものとして扱ったか
+    pub is_unknown_open: bool,
+}
+impl<const W: usize, const H: usize> Default for StepMap<W, H> {
+    fn default() -> Self {
+        Self {
+            steps: [[STEP_UNREACHABLE; W]; H],
+            connectivity: Connectivity::Four,
+            is_unknown_open: true,
+        }
+    }
+}
+impl<const W: usize, const H: usize> StepMap<W, H> {
+    /// ゴールまでの歩数を返します。到達できない、もしくは迷路外ならNone
+    pub fn get(&self, p: Point) -> Option<usize> {
+        if p.x >= W || p.y >= H || self.steps[p.y][p.x] == STEP_UNREACHABLE {
+            None
+        } else {
+            Some(usize::from(self.steps[p.y][p.x]))
+        }
+    }
+}
+
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// 現在の壁情報から歩数マップを作成します
+    /// 未確定の壁は存在しないものとして扱う
+    pub fn compute_step_map(&self, connectivity: Connectivity) -> StepMap<W, H> {
+        self.fill_step_map(self.goal.cells(), connectivity, true)
+    }
+
+    /// 確定済の壁情報だけで、区画toまでの歩数マップを作成します
+    /// 未確定の壁は存在するものとして扱うので、実際に通れる経路だけが残る
+    pub fn compute_step_map_to(&self, to: Point, connectivity: Connectivity) -> StepMap<W, H> {
+        self.fill_step_map(core::iter::once(to), connectivity, false)
+    }
+
+    /// startsを起点に、歩数の小さい区画から順に広げて歩数マップを作成します
+    fn fill_step_map(
+        &self,
+        starts: impl IntoIterator<Item = Point>,
+        connectivity: Connectivity,
+        is_unknown_open: bool,
+    ) -> StepMap<W, H> {
+        let mut dst = StepMap::<W, H> {
+            connectivity,
+            is_unknown_open,
+            ..StepMap::default()
+        };
+        let is_diagonal = connectivity == Connectivity::Eight;
+        // 斜めと上下左右で歩数が異なるので、少ない順に確定させる
+        let mut heap = SearchInfoHeap::<W, H>::default();
+        for g in starts {
+            if g.x < W && g.y < H && dst.steps[g.y][g.x] == STEP_UNREACHABLE {
+                dst.steps[g.y][g.x] = 0;
+                heap.push(g, 0);
+            }
+        }
+        while let Some(p) = heap.pop() {
+            let current = usize::from(dst.steps[p.y][p.x]);
+            for target_point in self.get_arounds(p, is_unknown_open, is_diagonal) {
+                let next_step = current + self.step_map_cost(connectivity, p, target_point);
+                let next_step = match u16::try_from(next_step) {
+                    Ok(s) if s < STEP_UNREACHABLE => s,
+                    _ => continue,
+                };
+                if next_step < dst.steps[target_point.y][target_point.x] {
+                    dst.steps[target_point.y][target_point.x] = next_step;
+                    heap.push(target_point, usize::from(next_step));
+                }
+            }
+        }
+        dst
+    }
+
+    /// 歩数マップで隣接する区画fromからtoへ進むときの歩数
+    /// Fourは常に1、Eightはstep_costに従う
+    fn step_map_cost(&self, connectivity: Connectivity, from: Point, to: Point) -> usize {
+        match connectivity {
+            Connectivity::Four => 1,
+            Connectivity::Eight => self.step_cost.get(from, to),
+        }
+    }
+
+    /// 歩数マップを下る方向の隣接区画を返します
+    /// 移動分を足した歩数が最も小さい区画で、複数あれば上、右、下、左、(斜め)の順で先に見つかったもの
+    /// ゴール区画にいる、もしくは到達できない場合はNone
+    pub fn get_step_next(&self, step_map: &StepMap<W, H>, p: Point) -> Option<Point> {
+        let current = step_map.get(p)?;
+        self.get_arounds(
+            p,
+            step_map.is_unknown_open,
+            step_map.connectivity == Connectivity::Eight,
+        )
+        .into_iter()
+        .filter_map(|target_point| step_map.get(target_point).map(|s| (target_point, s)))
+        .filter(|&(_, s)| s < current)
+        .min_by_key(|&(target_point, s)| {
+            s + self.step_map_cost(step_map.connectivity, p, target_point)
+        })
+        .map(|(target_point, _)| target_point)
+    }
+}
@@ -1,9 +1,14 @@
 use super::cell::*;
+use super::direction::Direction;
+use super::error::ExplorerError;
 use super::explorer::*;
+use super::frontier::Frontier;
 use super::point::Point;
+use super::wall::{WallConflict, WallState};
+use arrayvec::ArrayVec;
 
 /// 実機から迷路情報の更新に使う情報
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct UpdateInfo {
     /// 更新対象の区画
     pub p: Point,
@@ -12,91 +17,53 @@ pub struct UpdateInfo {
     pub left: Option<bool>,
     pub right: Option<bool>,
 }
-impl Default for UpdateInfo {
-    fn default() -> Self {
-        Self {
-            p: Point::default(),
-            up: None,
-            down: None,
-            left: None,
-            right: None,
-        }
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// 壁情報を更新する
+    /// 失敗した場合は何もしない。原因を知りたい場合はtry_updateを使う
+    pub fn update(&mut self, info: &UpdateInfo) {
+        let result = self.try_update(info);
+        debug_assert!(result.is_ok(), "{:?}", result);
     }
-}
 
-impl Explorer {
     /// 壁情報を更新する
-    pub fn update(&mut self, info: &UpdateInfo) {
-        debug_assert!(info.p.x < MAZE_WIDTH);
-        debug_assert!(info.p.y < MAZE_HEIGHT);
-        debug_assert!(!self.cells[info.p.y][info.p.x]
-            .flag
-            .contains(CellFlag::IS_UPDATED));
-        // 壁情報の更新
-        if let Some(up_wall) = info.up {
-            self.cells[info.p.y][info.p.x]
-                .flag
-                .insert(CellFlag::IS_UPDATED_UP_WALL);
-            if up_wall {
-                self.cells[info.p.y][info.p.x]
-                    .flag
-                    .insert(CellFlag::IS_EXISTS_UP_WALL);
-            } else {
-                self.cells[info.p.y][info.p.x]
-                    .flag
-                    .remove(CellFlag::IS_EXISTS_UP_WALL);
-            }
-        }
-        if let Some(right_wall) = info.right {
-            self.cells[info.p.y][info.p.x]
-                .flag
-                .insert(CellFlag::IS_UPDATED_RIGHT_WALL);
-            if right_wall {
-                self.cells[info.p.y][info.p.x]
-                    .flag
-                    .insert(CellFlag::IS_EXISTS_RIGHT_WALL);
-            } else {
-                self.cells[info.p.y][info.p.x]
-                    .flag
-                    .remove(CellFlag::IS_EXISTS_RIGHT_WALL);
-            }
+    /// 観測済の壁はwall_policyに従って反映し、食い違ったものを返す
+    /// 壁の状態が変わった場合は、その壁を通る経路に依存したコストだけを捨てて積み直す
+    /// 新しく壁がないとわかった場合は、そこを通れるようになった展開済の区画を展開し直す
+    /// 迷路外の区画が指定された場合はエラー
+    pub fn try_update(
+        &mut self,
+        info: &UpdateInfo,
+    ) -> Result<ArrayVec<WallConflict, 4>, ExplorerError> {
+        if info.p.x >= W || info.p.y >= H {
+            return Err(ExplorerError::OutOfBounds(info.p));
         }
-        // 下、左は隣のセル情報に格納されている
-        if info.p.y > 0 {
-            if let Some(down_wall) = info.down {
-                self.cells[info.p.y - 1][info.p.x]
-                    .flag
-                    .insert(CellFlag::IS_UPDATED_UP_WALL);
-                if down_wall {
-                    self.cells[info.p.y - 1][info.p.x]
-                        .flag
-                        .insert(CellFlag::IS_EXISTS_UP_WALL);
-                } else {
-                    self.cells[info.p.y - 1][info.p.x]
-                        .flag
-                        .remove(CellFlag::IS_EXISTS_UP_WALL);
-                }
-            }
-        }
-        if info.p.x > 0 {
-            if let Some(left_wall) = info.left {
-                self.cells[info.p.y][info.p.x - 1]
-                    .flag
-                    .insert(CellFlag::IS_UPDATED_RIGHT_WALL);
-                if left_wall {
-                    self.cells[info.p.y][info.p.x - 1]
-                        .flag
-                        .insert(CellFlag::IS_EXISTS_RIGHT_WALL);
-                } else {
-                    self.cells[info.p.y][info.p.x - 1]
-                        .flag
-                        .remove(CellFlag::IS_EXISTS_RIGHT_WALL);
-                }
+        // 壁情報の更新。下、左は隣の区画と共有している
+        let mut conflicts = ArrayVec::new();
+        let mut is_opened = false;
+        for (dir, exists) in [
+            (Direction::Up, info.up),
+            (Direction::Down, info.down),
+            (Direction::Left, info.left),
+            (Direction::Right, info.right),
+        ] {
+            let previous = self.wall(info.p, dir);
+            if let Some(conflict) = exists.and_then(|exists| self.observe_wall(info.p, dir, exists))
+            {
+                conflicts.push(conflict);
             }
+            is_opened |=
+                previous != WallState::Absent && self.wall(info.p, dir) == WallState::Absent;
         }
         // 探索済セルに追加
         self.cells[info.p.y][info.p.x]
             .flag
             .insert(CellFlag::IS_UPDATED);
+        // 壁が変わると、その壁を通っていたコストや経路は信用できない
+        if conflicts.iter().any(WallConflict::is_flipped) {
+            self.invalidate_search()?;
+        } else if is_opened {
+            self.expand_opened(info.p)?;
+        }
+        Ok(conflicts)
     }
 }
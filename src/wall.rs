@@ -0,0 +1,194 @@
+use super::cell::*;
+use super::direction::Direction;
+use super::explorer::*;
+use super::frontier::Frontier;
+use super::point::Point;
+
+/// 壁の状態
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WallState {
+    /// まだ観測していない
+    Unknown,
+    /// 壁がある
+    Present,
+    /// 壁がない
+    Absent,
+}
+impl WallState {
+    /// 壁の有無から変換します
+    pub fn from_exists(exists: bool) -> Self {
+        if exists {
+            WallState::Present
+        } else {
+            WallState::Absent
+        }
+    }
+}
+
+/// 壁ごとの観測回数。センサの誤読を多数決で打ち消すのに使う
+/// 区画ごとに2枚分持つので、上位4bitに壁あり、下位4bitに壁なしの回数を詰める
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct WallVotes(u8);
+impl WallVotes {
+    /// 数えられる回数の上限
+    pub const MAX: u8 = 0x0f;
+
+    /// 回数を指定して生成します。上限を超えた分は切り捨てる
+    pub fn new(present: u8, absent: u8) -> Self {
+        Self((present.min(Self::MAX) << 4) | absent.min(Self::MAX))
+    }
+    /// 壁があると観測した回数
+    pub fn present(&self) -> u8 {
+        self.0 >> 4
+    }
+    /// 壁がないと観測した回数
+    pub fn absent(&self) -> u8 {
+        self.0 & Self::MAX
+    }
+
+    /// 観測結果を1回分加えます
+    /// 上限に達していたら反対側を1つ減らすので、回数の差は上限がない場合と同じだけ動く
+    pub fn add(&mut self, exists: bool) {
+        let (present, absent) = (self.present(), self.absent());
+        *self = match (exists, present, absent) {
+            (true, Self::MAX, _) => Self::new(present, absent.saturating_sub(1)),
+            (true, _, _) => Self::new(present + 1, absent),
+            (false, _, Self::MAX) => Self::new(present.saturating_sub(1), absent),
+            (false, _, _) => Self::new(present, absent + 1),
+        };
+    }
+
+    /// 多い方の状態を返します。同数ならUnknown
+    pub fn majority(&self) -> WallState {
+        match self.present().cmp(&self.absent()) {
+            core::cmp::Ordering::Greater => WallState::Present,
+            core::cmp::Ordering::Less => WallState::Absent,
+            core::cmp::Ordering::Equal => WallState::Unknown,
+        }
+    }
+}
+
+/// 観測済の壁を再観測したときに、どの結果を採用するか
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum WallPolicy {
+    /// 最後の観測結果を採用する
+    #[default]
+    LastWins,
+    /// これまでの観測回数が多い方を採用する。同数なら現状維持
+    Majority,
+}
+/// 再観測の結果が、それまでの壁の状態と食い違ったもの
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct WallConflict {
+    /// 観測した区画
+    pub p: Point,
+    /// 観測した方向
+    pub dir: Direction,
+    /// 観測前の状態
+    pub previous: WallState,
+    /// 観測結果を反映した後の状態。policyによってはpreviousのまま
+    pub current: WallState,
+}
+impl WallConflict {
+    /// 壁の状態が変わっていればtrue
+    pub fn is_flipped(&self) -> bool {
+        self.previous != self.current
+    }
+}
+
+impl<const W: usize, const H: usize, F: Frontier> Explorer<W, H, F> {
+    /// 壁が格納されている区画とフラグ(確定済, 存在)を返します。外周はNone
+    /// 下、左の壁は隣の区画の上、右の壁として格納されている
+    fn wall_index(p: Point, dir: Direction) -> Option<(Point, CellFlag, CellFlag)> {
+        const UP: (CellFlag, CellFlag) =
+            (CellFlag::IS_UPDATED_UP_WALL, CellFlag::IS_EXISTS_UP_WALL);
+        const RIGHT: (CellFlag, CellFlag) = (
+            CellFlag::IS_UPDATED_RIGHT_WALL,
+            CellFlag::IS_EXISTS_RIGHT_WALL,
+        );
+        let neighbor = p.neighbor::<W, H>(dir)?;
+        let (cell, (updated, exists)) = match dir {
+            Direction::Up => (p, UP),
+            Direction::Right => (p, RIGHT),
+            Direction::Down => (neighbor, UP),
+            Direction::Left => (neighbor, RIGHT),
+            _ => return None,
+        };
+        Some((cell, updated, exists))
+    }
+
+    /// pからdir方向(上下左右)の壁の状態を返します
+    /// 外周と、上下左右以外の方向は常にPresent
+    pub fn wall(&self, p: Point, dir: Direction) -> WallState {
+        debug_assert!(p.x < W && p.y < H);
+        match Self::wall_index(p, dir) {
+            Some((cell, updated, exists)) => {
+                let flag = self.cells[cell.y][cell.x].flag;
+                if !flag.contains(updated) {
+                    WallState::Unknown
+                } else {
+                    WallState::from_exists(flag.contains(exists))
+                }
+            }
+            None => WallState::Present,
+        }
+    }
+
+    /// pからdir方向(上下左右)の壁の状態を設定します
+    /// 隣の区画から見た壁も同じものになる。外周は変更できない
+    pub fn set_wall(&mut self, p: Point, dir: Direction, state: WallState) {
+        debug_assert!(p.x < W && p.y < H);
+        if let Some((cell, updated, exists)) = Self::wall_index(p, dir) {
+            let flag = &mut self.cells[cell.y][cell.x].flag;
+            flag.set(updated, state != WallState::Unknown);
+            flag.set(exists, state == WallState::Present);
+        }
+    }
+
+    /// pからdir方向(上下左右)の壁の観測回数を返します。外周はNone
+    pub fn wall_votes(&self, p: Point, dir: Direction) -> Option<WallVotes> {
+        let (cell, updated, _) = Self::wall_index(p, dir)?;
+        let c = &self.cells[cell.y][cell.x];
+        if updated == CellFlag::IS_UPDATED_UP_WALL {
+            Some(c.up_votes)
+        } else {
+            Some(c.right_votes)
+        }
+    }
+
+    /// pからdir方向(上下左右)の壁を観測した結果を、wall_policyに従って反映します
+    /// 観測済の壁と食い違った場合はその内容を返す。外周は何もしない
+    pub fn observe_wall(&mut self, p: Point, dir: Direction, exists: bool) -> Option<WallConflict> {
+        let (cell, updated, _) = Self::wall_index(p, dir)?;
+        let previous = self.wall(p, dir);
+        let c = &mut self.cells[cell.y][cell.x];
+        let votes = if updated == CellFlag::IS_UPDATED_UP_WALL {
+            &mut c.up_votes
+        } else {
+            &mut c.right_votes
+        };
+        votes.add(exists);
+        let observed = WallState::from_exists(exists);
+        let current = match self.wall_policy {
+            WallPolicy::LastWins => observed,
+            // 同数なら現状維持。未観測なら今回の結果
+            WallPolicy::Majority => match votes.majority() {
+                WallState::Unknown if previous != WallState::Unknown => previous,
+                WallState::Unknown => observed,
+                majority => majority,
+            },
+        };
+        self.set_wall(p, dir, current);
+
+        if previous == WallState::Unknown || previous == observed {
+            None
+        } else {
+            Some(WallConflict {
+                p,
+                dir,
+                previous,
+                current,
+            })
+        }
+    }
+}
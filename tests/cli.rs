@@ -0,0 +1,74 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{self, Command, Output};
+
+/// 生成した16x16迷路の.maz
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/generated_16x16.maz"
+);
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_oratosquilla"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// 同時に走る他のテストと重ならない一時ファイル
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("oratosquilla_{}_{}", process::id(), name))
+}
+
+/// .mazを読み込んで最短経路を表示するか
+#[test]
+pub fn cli_solve() {
+    let output = run(&["solve", FIXTURE]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "cost: 15");
+    assert_eq!(lines[1], "0 0");
+    assert_eq!(lines.len(), 1 + 16);
+    let last = lines[lines.len() - 1];
+    assert!(["7 7", "8 7", "7 8", "8 8"].contains(&last));
+}
+
+/// シミュレータで探索しきった最小コストが、既知の迷路での最短と一致するか
+#[test]
+pub fn cli_simulate() {
+    let output = run(&["simulate", FIXTURE]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("goal reached: "));
+    assert_eq!(stdout.lines().last(), Some("cost: 15"));
+}
+
+/// mazefiles形式を経由して変換し直しても.mazが変わらないか
+#[test]
+pub fn cli_convert() {
+    let text = temp_path("convert.txt");
+    let maz = temp_path("convert.maz");
+    let output = run(&["convert", FIXTURE, text.to_str().unwrap()]);
+    assert!(output.status.success());
+    let output = run(&["convert", text.to_str().unwrap(), maz.to_str().unwrap()]);
+    assert!(output.status.success());
+
+    // renderはconvertで書き出すmazefiles形式と同じ
+    let output = run(&["render", FIXTURE]);
+    assert_eq!(output.stdout, fs::read(&text).unwrap());
+    assert_eq!(fs::read(&maz).unwrap(), fs::read(FIXTURE).unwrap());
+    fs::remove_file(text).unwrap();
+    fs::remove_file(maz).unwrap();
+}
+
+/// 引数が足りなければ使い方を表示して失敗するか
+#[test]
+pub fn cli_usage() {
+    let output = run(&["solve"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("usage: "));
+}
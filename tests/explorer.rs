@@ -0,0 +1,1448 @@
+#![allow(
+    clippy::field_reassign_with_default,
+    clippy::bool_assert_comparison,
+    clippy::doc_lazy_continuation
+)]
+extern crate oratosquilla;
+
+use oratosquilla::prelude::*;
+
+/// すべての区画を観測済にする
+/// 壁ありになるのは外周と、is_wallがtrueを返した上、右の壁
+fn update_all_walls<const W: usize, const H: usize, F: Frontier>(
+    m: &mut Explorer<W, H, F>,
+    is_wall: impl Fn(Point, Direction) -> bool,
+) {
+    for y in 0..H {
+        for x in 0..W {
+            let p = Point { x, y };
+            let mut info = UpdateInfo::default();
+            info.p = p;
+            info.up = Some(y == H - 1 || is_wall(p, Direction::Up));
+            info.right = Some(x == W - 1 || is_wall(p, Direction::Right));
+            m.update(&info);
+        }
+    }
+}
+
+/// pの区画に入ったときに見える壁情報
+/// 壁ありになるのはupdate_all_wallsと同じく外周と、is_wallがtrueを返した上、右の壁
+fn observe_cell<const W: usize, const H: usize>(
+    p: Point,
+    is_wall: impl Fn(Point, Direction) -> bool,
+) -> UpdateInfo {
+    let mut info = UpdateInfo::default();
+    info.p = p;
+    info.up = Some(p.y == H - 1 || is_wall(p, Direction::Up));
+    info.right = Some(p.x == W - 1 || is_wall(p, Direction::Right));
+    info.down = Some(p.y == 0 || is_wall(Point { x: p.x, y: p.y - 1 }, Direction::Up));
+    info.left = Some(p.x == 0 || is_wall(Point { x: p.x - 1, y: p.y }, Direction::Right));
+    info
+}
+
+/// 最初の地点から一歩進むか
+#[test]
+pub fn ahead_start() {
+    let mut m: Explorer = Explorer::new(Point { x: 10, y: 10 });
+
+    let p = Point { x: 0, y: 0 };
+    let mut info = UpdateInfo::default();
+    info.p = p;
+    info.up = Some(false);
+    info.down = None;
+    info.left = Some(true);
+    info.right = Some(true);
+    m.update(&info);
+    m.fetch_targets(p);
+    let next_p = m.get_next();
+    m.debug_print("test.log", "ahead_start").unwrap();
+
+    assert_eq!(next_p.is_some(), true);
+    assert_eq!(next_p.unwrap().x, 0);
+    assert_eq!(next_p.unwrap().y, 1);
+}
+
+/// 最初の地点で2マス更新をかけた際に、斜めマスを最短とするか
+/// .++++.++++.????
+/// +         +    
+/// +   1    1+    
+/// .    .++++.????
+/// + SS +    ?    
+/// +   0+    ?    
+/// .++++.++++.++++
+#[test]
+pub fn diagonal_start() {
+    let mut m: Explorer = Explorer::new(Point { x: 10, y: 10 });
+
+    let p = Point { x: 0, y: 0 };
+    let mut info = UpdateInfo::default();
+    info.p = p;
+    info.up = Some(false);
+    info.down = None;
+    info.left = Some(true);
+    info.right = Some(true);
+    m.update(&info);
+
+    let mut info = UpdateInfo::default();
+    info.p = Point { x: 0, y: 1 };
+    info.up = Some(true);
+    info.down = None;
+    info.left = Some(true);
+    info.right = Some(false);
+    m.update(&info);
+
+    m.fetch_targets(p);
+    let next_p = m.get_next();
+    m.debug_print("test.log", "diagonal_start").unwrap();
+
+    assert_eq!(next_p.is_some(), true);
+    assert_eq!(next_p.unwrap().x, 1);
+    assert_eq!(next_p.unwrap().y, 1);
+}
+
+/// 最初の地点で2マス更新をかけた際に、斜めマスを最短としてさらに探索を続けるか
+///        ↓ここ
+/// .????.????.????.????.
+/// +    ?    ?    ?    ?
+/// +    ?   2?    ?    ?
+/// .++++.    .????.????.
+/// +              ?    ?
+/// +   1    1    2?    ?
+/// .    .    .????.????.
+/// + SS +    ?    ?    ?
+/// +   0+   2?    ?    ?
+/// .++++.++++.++++.++++.
+#[test]
+pub fn diagonal_start_2() {
+    let mut m: Explorer = Explorer::new(Point { x: 10, y: 10 });
+
+    let p = Point { x: 0, y: 0 };
+    let mut info = UpdateInfo::default();
+    info.p = p;
+    info.up = Some(false);
+    info.down = None;
+    info.left = Some(true);
+    info.right = Some(true);
+    m.update(&info);
+
+    let mut info = UpdateInfo::default();
+    info.p = Point { x: 0, y: 1 };
+    info.up = Some(true);
+    info.down = None;
+    info.left = Some(true);
+    info.right = Some(false);
+    m.update(&info);
+
+    m.fetch_targets(p);
+    let next_p = m.get_next().unwrap();
+    info.p = next_p;
+    info.up = Some(false);
+    info.down = Some(false);
+    info.left = None;
+    info.right = Some(false);
+    m.update(&info);
+    m.fetch_targets(next_p);
+
+    let last_p = m.get_next();
+    m.debug_print("test.log", "diagonal_start_2").unwrap();
+
+    assert_eq!(last_p.is_some(), true);
+    // 上が最短になるはず
+    assert_eq!(last_p.unwrap().x, 1);
+    assert_eq!(last_p.unwrap().y, 2);
+}
+
+/// ゴールしてかつ他に探索可能区間がない場合に探索が止まるか
+#[test]
+pub fn goal_and_stop() {
+    let mut m: Explorer = Explorer::new(Point { x: 0, y: 1 });
+
+    let p = Point { x: 0, y: 0 };
+    let mut info = UpdateInfo::default();
+    info.p = p;
+    info.up = Some(false);
+    info.down = None;
+    info.left = Some(true);
+    info.right = Some(true);
+    m.update(&info);
+    m.fetch_targets(p);
+
+    let next_p = m.get_next().unwrap();
+    info.p = next_p;
+    info.up = Some(true);
+    info.down = None;
+    info.left = Some(true);
+    info.right = Some(true);
+    m.update(&info);
+    m.fetch_targets(next_p);
+
+    let last_p = m.get_next();
+    m.debug_print("test.log", "goal_and_stop").unwrap();
+
+    assert_eq!(last_p.is_some(), false);
+}
+
+/// 一切壁がなく右端がゴールの場合に最短距離ですすめるか
+#[test]
+pub fn no_wall_move_x() {
+    let mut m: Explorer = Explorer::new(Point {
+        x: MAZE_WIDTH - 1,
+        y: 0,
+    });
+
+    let mut p = Point { x: 0, y: 0 };
+    for _i in 0..MAZE_WIDTH - 1 {
+        let mut info = UpdateInfo::default();
+        info.p = p;
+        info.up = Some(false);
+        info.down = Some(false);
+        info.left = Some(false);
+        info.right = Some(false);
+        m.update(&info);
+        m.fetch_targets(p);
+        p = m.get_next().unwrap();
+        // m.debug_print("no_wall_move_x.log", "index").unwrap();
+    }
+    m.debug_print("test.log", "no_wall_move_x").unwrap();
+
+    assert_eq!(p.x, MAZE_WIDTH - 1);
+    assert_eq!(p.y, 0);
+}
+
+/// 一切壁がなく上端がゴールの場合に最短距離ですすめるか
+#[test]
+pub fn no_wall_move_y() {
+    let mut m: Explorer = Explorer::new(Point {
+        x: 0,
+        y: MAZE_HEIGHT - 1,
+    });
+
+    let mut p = Point { x: 0, y: 0 };
+    for _i in 0..MAZE_HEIGHT - 1 {
+        let mut info = UpdateInfo::default();
+        info.p = p;
+        info.up = Some(false);
+        info.down = Some(false);
+        info.left = Some(false);
+        info.right = Some(false);
+        m.update(&info);
+        m.fetch_targets(p);
+        p = m.get_next().unwrap();
+        // m.debug_print("no_wall_move_y.log", "index").unwrap();
+    }
+    m.debug_print("test.log", "no_wall_move_y").unwrap();
+
+    assert_eq!(p.x, 0);
+    assert_eq!(p.y, MAZE_HEIGHT - 1);
+}
+/// 一切壁がなく右上端がゴールの場合に最短距離ですすめるか
+#[test]
+pub fn no_wall_move_xy() {
+    let mut m: Explorer = Explorer::new(Point {
+        x: MAZE_WIDTH - 1,
+        y: MAZE_HEIGHT - 1,
+    });
+
+    let mut p = Point { x: 0, y: 0 };
+    for _i in 0..31 {
+        let mut info = UpdateInfo::default();
+        info.p = p;
+        info.up = Some(false);
+        info.down = Some(false);
+        info.left = Some(false);
+        info.right = Some(false);
+        m.update(&info);
+        // 斜め走行を最初から出すためには隣のマスの情報が必要
+        if p.x < MAZE_WIDTH - 1 {
+            info.p = Point { x: p.x + 1, y: p.y };
+            info.up = Some(false);
+            info.down = Some(false);
+            info.left = Some(false);
+            info.right = Some(p.x >= MAZE_WIDTH - 2);
+            m.update(&info);
+        }
+        if p.y < MAZE_HEIGHT - 1 {
+            info.p = Point { x: p.x, y: p.y + 1 };
+            info.up = Some(p.y >= MAZE_HEIGHT - 2);
+            info.down = Some(false);
+            info.left = Some(false);
+            info.right = Some(false);
+            m.update(&info);
+        }
+
+        m.fetch_targets(p);
+        p = m.get_next().unwrap();
+        // m.debug_print("no_wall_move_xy.log", "index").unwrap();
+    }
+    m.debug_print("test.log", "no_wall_move_xy").unwrap();
+
+    assert_eq!(p.x, MAZE_WIDTH - 1);
+    assert_eq!(p.y, MAZE_HEIGHT - 1);
+}
+
+/// 16x16迷路でも右上端まで最短距離ですすめるか
+#[test]
+pub fn no_wall_move_xy_16() {
+    const W: usize = 16;
+    const H: usize = 16;
+    let mut m = Explorer::<W, H>::new(Point { x: W - 1, y: H - 1 });
+
+    let mut p = Point { x: 0, y: 0 };
+    for _i in 0..W - 1 {
+        let mut info = UpdateInfo::default();
+        info.p = p;
+        info.up = Some(false);
+        info.down = Some(false);
+        info.left = Some(false);
+        info.right = Some(false);
+        m.update(&info);
+        if p.x < W - 1 {
+            info.p = Point { x: p.x + 1, y: p.y };
+            info.up = Some(false);
+            info.down = Some(false);
+            info.left = Some(false);
+            info.right = Some(p.x >= W - 2);
+            m.update(&info);
+        }
+        if p.y < H - 1 {
+            info.p = Point { x: p.x, y: p.y + 1 };
+            info.up = Some(p.y >= H - 2);
+            info.down = Some(false);
+            info.left = Some(false);
+            info.right = Some(false);
+            m.update(&info);
+        }
+
+        m.fetch_targets(p);
+        p = m.get_next().unwrap();
+    }
+    m.debug_print("test.log", "no_wall_move_xy_16").unwrap();
+
+    assert_eq!(p.x, W - 1);
+    assert_eq!(p.y, H - 1);
+}
+
+/// 複数区画のゴールで、最寄りの区画に到達した時点でゴールとするか
+#[test]
+pub fn goal_rect_reached() {
+    let mut m = Explorer::<16, 16>::new(Goal::rect(Point { x: 1, y: 2 }, Point { x: 0, y: 1 }));
+
+    // ゴール内部の壁は無いものとして扱う
+    assert!(m.cells[1][0].flag.contains(CellFlag::IS_UPDATED_UP_WALL));
+    assert!(!m.cells[1][0].flag.contains(CellFlag::IS_EXISTS_UP_WALL));
+    assert!(m.cells[1][0].flag.contains(CellFlag::IS_UPDATED_RIGHT_WALL));
+    assert!(!m.cells[1][0].flag.contains(CellFlag::IS_EXISTS_RIGHT_WALL));
+    assert!(!m.cells[2][1].flag.contains(CellFlag::IS_UPDATED_UP_WALL));
+    assert!(!m.cells[0][0].flag.contains(CellFlag::IS_UPDATED_UP_WALL));
+
+    let p = Point { x: 0, y: 0 };
+    let mut info = UpdateInfo::default();
+    info.p = p;
+    info.up = Some(false);
+    info.left = Some(true);
+    info.right = Some(true);
+    m.update(&info);
+    m.fetch_targets(p);
+    m.debug_print("test.log", "goal_rect_reached").unwrap();
+
+    assert_eq!(m.min_cost, Some(1));
+    assert_eq!(m.get_next(), Some(Point { x: 0, y: 1 }));
+}
+
+/// 任意区画のゴールに対して最寄りの距離を返すか
+#[test]
+pub fn goal_cells_distance() {
+    let goal = Goal::from_cells(&[Point { x: 7, y: 7 }, Point { x: 8, y: 8 }]).unwrap();
+
+    assert!(goal.contains(Point { x: 8, y: 8 }));
+    assert!(!goal.contains(Point { x: 7, y: 8 }));
+    assert_eq!(goal.estimate(Point { x: 0, y: 0 }, &StepCost::default()), 7);
+    assert_eq!(
+        goal.estimate(Point { x: 10, y: 9 }, &StepCost::default()),
+        2
+    );
+    assert_eq!(goal.cells().count(), 2);
+
+    let rect = Goal::rect(Point { x: 7, y: 7 }, Point { x: 8, y: 8 });
+    assert_eq!(rect.estimate(Point { x: 0, y: 0 }, &StepCost::default()), 7);
+    assert_eq!(rect.estimate(Point { x: 8, y: 3 }, &StepCost::default()), 4);
+    let weighted = StepCost::new(10, 14).unwrap();
+    assert_eq!(rect.estimate(Point { x: 0, y: 0 }, &weighted), 98);
+    assert_eq!(rect.estimate(Point { x: 8, y: 3 }, &weighted), 40);
+    assert_eq!(rect.cells().count(), 4);
+}
+
+/// ゴールからスタートまでの経路を復元できるか
+#[test]
+pub fn trace_answer_straight() {
+    let mut m = Explorer::<16, 16>::new(Point { x: 0, y: 2 });
+
+    let mut p = Point { x: 0, y: 0 };
+    for _i in 0..2 {
+        let mut info = UpdateInfo::default();
+        info.p = p;
+        info.up = Some(false);
+        info.left = Some(true);
+        info.right = Some(true);
+        m.update(&info);
+        m.fetch_targets(p);
+        p = m.get_next().unwrap();
+    }
+    let path = m.trace_answer::<16>().unwrap();
+    m.debug_print("test.log", "trace_answer_straight").unwrap();
+
+    assert_eq!(
+        path.as_slice(),
+        &[
+            Point { x: 0, y: 0 },
+            Point { x: 0, y: 1 },
+            Point { x: 0, y: 2 }
+        ]
+    );
+    assert!(m.cells[1][0].flag.contains(CellFlag::IS_ANSWER));
+    assert!(!m.cells[1][1].flag.contains(CellFlag::IS_ANSWER));
+    // 容量不足はエラーになり、フラグも残らない
+    assert_eq!(
+        m.trace_answer::<2>().unwrap_err(),
+        AnswerError::CapacityExceeded
+    );
+    assert!(!m.cells[1][0].flag.contains(CellFlag::IS_ANSWER));
+}
+
+/// from_infoが壊れている、もしくは循環している場合にエラーを返すか
+#[test]
+pub fn trace_answer_invalid_chain() {
+    let mut m = Explorer::<16, 16>::new(Point { x: 1, y: 1 });
+    assert_eq!(
+        m.trace_answer::<16>().unwrap_err(),
+        AnswerError::GoalNotReached
+    );
+
+    m.cells[1][1].update_cost(2, SearchInfo::default());
+    assert_eq!(
+        m.trace_answer::<16>().unwrap_err(),
+        AnswerError::BrokenChain(Point { x: 1, y: 1 })
+    );
+
+    m.cells[1][0].update_cost(1, SearchInfo::from(Point { x: 1, y: 1 }));
+    m.cells[1][1].from_info = SearchInfo::from(Point { x: 0, y: 1 });
+    assert_eq!(
+        m.trace_answer::<16>().unwrap_err(),
+        AnswerError::Cyclic(Point { x: 1, y: 1 })
+    );
+}
+
+/// 展開済の区画より短いルートが見つかった際に、子孫のコストまで更新されるか
+#[test]
+pub fn relax_dirty_cost() {
+    let mut m = Explorer::<16, 16>::new(Point { x: 0, y: 4 });
+
+    // 左端の1列だけの通路
+    for y in 0..5 {
+        let mut info = UpdateInfo::default();
+        info.p = Point { x: 0, y };
+        info.up = Some(y == 4);
+        info.down = if y == 0 { None } else { Some(false) };
+        info.left = Some(true);
+        info.right = Some(true);
+        m.update(&info);
+    }
+    // 遠回りで到達したことにしておく
+    for y in 1..5 {
+        m.cells[y][0].update_cost(y + 4, SearchInfo::from(Point { x: 0, y: y - 1 }));
+        m.cells[y][0].flag.insert(CellFlag::IS_SEARCH_AROUND);
+    }
+    m.min_cost = Some(8);
+    // (0,1)だけ短いルートが見つかった
+    m.cells[1][0].update_cost(1, SearchInfo::from(Point { x: 0, y: 0 }));
+    assert!(m.cells[1][0].flag.contains(CellFlag::IS_COST_DIRTY));
+
+    m.relax_costs().unwrap();
+    m.debug_print("test.log", "relax_dirty_cost").unwrap();
+
+    for y in 0..5 {
+        assert_eq!(m.cells[y][0].cost, y);
+        assert!(!m.cells[y][0].flag.contains(CellFlag::IS_COST_DIRTY));
+    }
+    assert_eq!(m.min_cost, Some(4));
+    assert_eq!(m.trace_answer::<8>().unwrap().len(), 5);
+}
+
+/// 展開するたびに壁を観測しても、観測済の壁で探索し直した最短と一致するか
+/// 新しく壁がないとわかると、展開済の区画から斜めに移動できるようになることがある
+#[test]
+pub fn relax_observed_walls() {
+    const W: usize = 16;
+    const H: usize = 16;
+    let walls: [fn(Point, Direction) -> bool; 3] = [
+        |_, _| false,
+        |p, dir| (p.x * 7 + p.y * 13 + if dir == Direction::Up { 5 } else { 0 }) % 4 == 0,
+        |p, dir| dir == Direction::Right && p.x == 7 && p.y < H - 1,
+    ];
+    for is_wall in walls {
+        let mut m = Explorer::<W, H>::new(Point { x: W - 1, y: H - 1 });
+        while let Some(p) = m.get_next() {
+            if !m.cells[p.y][p.x].flag.contains(CellFlag::IS_UPDATED) {
+                m.update(&observe_cell::<W, H>(p, is_wall));
+            }
+            m.fetch_targets(p);
+        }
+
+        let mut fresh = Explorer::<W, H>::new(m.goal.clone());
+        for y in 0..H {
+            for x in 0..W {
+                if m.cells[y][x].flag.contains(CellFlag::IS_UPDATED) {
+                    fresh.update(&observe_cell::<W, H>(Point { x, y }, is_wall));
+                }
+            }
+        }
+        while let Some(p) = fresh.get_next() {
+            fresh.fetch_targets(p);
+        }
+        assert!(m.min_cost.is_some());
+        assert_eq!(m.min_cost, fresh.min_cost);
+    }
+}
+
+/// 壁情報がすべて既知の場合に、f値の小さい順に取り出して最短経路を見つけるか
+#[test]
+pub fn heap_search_optimal() {
+    const W: usize = 16;
+    const H: usize = 16;
+    let mut m = Explorer::<W, H>::new(Point { x: W - 1, y: 0 });
+
+    // x=7の右側に上端以外をふさぐ壁
+    update_all_walls(&mut m, |p, dir| {
+        dir == Direction::Right && p.x == 7 && p.y < H - 1
+    });
+
+    let mut p = m.start;
+    let mut last_cost = 0;
+    loop {
+        m.fetch_targets(p);
+        match m.get_next() {
+            Some(next_p) => {
+                let cost = m.cells[next_p.y][next_p.x].cost + m.goal.estimate(next_p, &m.step_cost);
+                assert!(last_cost <= cost);
+                last_cost = cost;
+                p = next_p;
+            }
+            None => break,
+        }
+    }
+    let path = m.trace_answer::<{ W * H }>().unwrap();
+    m.debug_print("test.log", "heap_search_optimal").unwrap();
+
+    assert_eq!(m.min_cost, Some(30));
+    assert_eq!(path.len(), 31);
+}
+
+/// 積み済の区画を積み直しても重複せず、優先度だけ付け替わるか
+#[test]
+pub fn heap_decrease_key() {
+    let mut heap = SearchInfoHeap::<2, 2>::default();
+    for (i, p) in points(&[(0, 0), (1, 0), (0, 1), (1, 1)])
+        .into_iter()
+        .enumerate()
+    {
+        assert!(heap.try_push(p, 10 + i).is_ok());
+    }
+    assert_eq!(heap.get_free(), 0);
+
+    // 満杯でも積み済の区画なら付け替えられる
+    assert!(heap.contains(Point { x: 1, y: 1 }));
+    assert!(heap.try_push(Point { x: 1, y: 1 }, 1).is_ok());
+    assert!(heap.try_push(Point { x: 1, y: 0 }, 20).is_ok());
+    assert_eq!(heap.get_count(), 4);
+
+    assert_eq!(heap.pop(), Some(Point { x: 1, y: 1 }));
+    assert!(!heap.contains(Point { x: 1, y: 1 }));
+    assert_eq!(heap.pop(), Some(Point { x: 0, y: 0 }));
+    assert_eq!(heap.pop(), Some(Point { x: 0, y: 1 }));
+    assert_eq!(heap.pop(), Some(Point { x: 1, y: 0 }));
+    assert_eq!(heap.pop(), None);
+}
+
+/// 区画数と同じ容量しかない小さな迷路でも、積み直しを重ねて最後まで探索できるか
+#[test]
+pub fn heap_search_small_maze() {
+    for (step_cost, goal, min_cost) in [
+        (StepCost::default(), Point { x: 2, y: 2 }, 2),
+        (StepCost::default(), Point { x: 1, y: 1 }, 1),
+        (StepCost::new(10, 14).unwrap(), Point { x: 2, y: 2 }, 28),
+        (StepCost::new(10, 14).unwrap(), Point { x: 2, y: 1 }, 24),
+    ] {
+        let mut m = Explorer::<3, 3>::new(goal);
+        m.step_cost = step_cost;
+        m.reset_search();
+        update_all_walls(&mut m, |_, _| false);
+        while let Some(p) = m.get_next() {
+            assert_eq!(m.try_fetch_targets(p), Ok(()));
+            assert!(m.provider.get_count() <= SearchInfoHeap::<3, 3>::CAPACITY);
+        }
+        assert_eq!(m.min_cost, Some(min_cost));
+    }
+}
+
+/// 探索戦略を差し替えて同じ迷路を探索する
+fn search_wall_maze<F: Frontier + Default>() -> Explorer<16, 16, F> {
+    const W: usize = 16;
+    const H: usize = 16;
+    let mut m = Explorer::<W, H, F>::new(Point { x: W - 1, y: 0 });
+
+    // x=7の右側に上端以外をふさぐ壁
+    update_all_walls(&mut m, |p, dir| {
+        dir == Direction::Right && p.x == 7 && p.y < H - 1
+    });
+    let mut p = m.start;
+    loop {
+        m.fetch_targets(p);
+        match m.get_next() {
+            Some(next_p) => p = next_p,
+            None => break,
+        }
+    }
+    m
+}
+
+/// どの探索戦略でも探索しきれば最短コストが一致するか
+#[test]
+pub fn frontier_strategies() {
+    let mut stack = search_wall_maze::<SearchInfoProvider<16, 16>>();
+    let mut queue = search_wall_maze::<SearchInfoQueue<16, 16>>();
+    let mut heap = search_wall_maze::<SearchInfoHeap<16, 16>>();
+    stack
+        .debug_print("test.log", "frontier_strategies stack")
+        .unwrap();
+    queue
+        .debug_print("test.log", "frontier_strategies queue")
+        .unwrap();
+    heap.debug_print("test.log", "frontier_strategies heap")
+        .unwrap();
+
+    assert_eq!(stack.min_cost, Some(30));
+    assert_eq!(queue.min_cost, Some(30));
+    assert_eq!(heap.min_cost, Some(30));
+    assert_eq!(stack.trace_answer::<256>().unwrap().len(), 31);
+    assert_eq!(queue.trace_answer::<256>().unwrap().len(), 31);
+    assert_eq!(heap.trace_answer::<256>().unwrap().len(), 31);
+}
+
+/// 各Frontierの取り出し順
+#[test]
+pub fn frontier_order() {
+    let points = [
+        (Point { x: 0, y: 0 }, 3),
+        (Point { x: 1, y: 0 }, 1),
+        (Point { x: 2, y: 0 }, 2),
+    ];
+    let mut stack = SearchInfoProvider::<4, 4>::default();
+    let mut queue = SearchInfoQueue::<4, 4>::default();
+    let mut heap = SearchInfoHeap::<4, 4>::default();
+    for (p, priority) in &points {
+        assert!(stack.push(*p, *priority));
+        assert!(queue.push(*p, *priority));
+        assert!(heap.push(*p, *priority));
+    }
+    assert_eq!(stack.get_count(), 3);
+    assert_eq!(queue.get_free(), 13);
+
+    assert_eq!(stack.pop().unwrap().x, 2);
+    assert_eq!(stack.pop().unwrap().x, 1);
+    assert_eq!(stack.pop().unwrap().x, 0);
+    assert_eq!(queue.pop().unwrap().x, 0);
+    assert_eq!(queue.pop().unwrap().x, 1);
+    assert_eq!(queue.pop().unwrap().x, 2);
+    assert_eq!(heap.pop().unwrap().x, 1);
+    assert_eq!(heap.pop().unwrap().x, 2);
+    assert_eq!(heap.pop().unwrap().x, 0);
+    assert!(stack.pop().is_none() && queue.pop().is_none() && heap.pop().is_none());
+
+    // リングバッファの折り返し
+    for i in 0..16 {
+        assert!(queue.push(Point { x: i % 4, y: i / 4 }, 0));
+    }
+    for i in 0..16 {
+        assert_eq!(queue.pop(), Some(Point { x: i % 4, y: i / 4 }));
+    }
+}
+
+/// 区画数ちょうどまで積めて、それ以上はFrontierFull、迷路外はOutOfBoundsになるか
+#[test]
+pub fn frontier_capacity() {
+    let mut stack = SearchInfoProvider::<2, 2>::default();
+    let mut queue = SearchInfoQueue::<2, 2>::default();
+    for p in points(&[(0, 0), (1, 0), (0, 1), (1, 1)]) {
+        assert_eq!(stack.try_push(p, 0), Ok(()));
+        assert_eq!(queue.try_push(p, 0), Ok(()));
+    }
+    assert_eq!(stack.get_free(), 0);
+    assert_eq!(queue.get_free(), 0);
+    let p = Point { x: 0, y: 0 };
+    assert_eq!(stack.try_push(p, 0), Err(ExplorerError::FrontierFull));
+    assert_eq!(queue.try_push(p, 0), Err(ExplorerError::FrontierFull));
+    assert_eq!(stack.get_count(), SearchInfoProvider::<2, 2>::CAPACITY);
+
+    // 最後に積んだものも取り出せる
+    assert_eq!(stack.pop(), Some(Point { x: 1, y: 1 }));
+    assert_eq!(stack.try_push(p, 0), Ok(()));
+    assert_eq!(stack.pop(), Some(p));
+
+    // 迷路外の区画は積まない
+    let mut heap = SearchInfoHeap::<2, 2>::default();
+    for p in points(&[(2, 0), (0, 2)]) {
+        assert_eq!(stack.try_push(p, 0), Err(ExplorerError::OutOfBounds(p)));
+        assert_eq!(queue.try_push(p, 0), Err(ExplorerError::OutOfBounds(p)));
+        assert_eq!(heap.try_push(p, 0), Err(ExplorerError::OutOfBounds(p)));
+    }
+    assert_eq!(heap.get_count(), 0);
+}
+
+/// 歩数マップが未知の壁を通れるものとして歩数を数えるか。8方向はstep_costに従うか
+#[test]
+pub fn step_map_unknown() {
+    let mut m = Explorer::<16, 16>::new(Goal::rect(Point { x: 7, y: 7 }, Point { x: 8, y: 8 }));
+
+    let four = m.compute_step_map(Connectivity::Four);
+    let eight = m.compute_step_map(Connectivity::Eight);
+
+    assert_eq!(four.get(Point { x: 0, y: 0 }), Some(14));
+    assert_eq!(four.get(Point { x: 8, y: 7 }), Some(0));
+    assert_eq!(four.get(Point { x: 15, y: 15 }), Some(14));
+    assert_eq!(eight.get(Point { x: 0, y: 0 }), Some(7));
+    assert_eq!(eight.get(Point { x: 15, y: 0 }), Some(7));
+    assert_eq!(four.get(Point { x: 16, y: 0 }), None);
+
+    // 8方向はstep_costで数える
+    m.step_cost = StepCost::new(10, 14).unwrap();
+    let eight = m.compute_step_map(Connectivity::Eight);
+    assert_eq!(eight.get(Point { x: 0, y: 0 }), Some(7 * 14));
+    assert_eq!(eight.get(Point { x: 7, y: 0 }), Some(7 * 10));
+    assert_eq!(eight.get(Point { x: 0, y: 2 }), Some(5 * 14 + 2 * 10));
+    let next = m.get_step_next(&eight, Point { x: 0, y: 2 }).unwrap();
+    assert_eq!(
+        eight.get(next).unwrap() + m.step_cost.get(Point { x: 0, y: 2 }, next),
+        90
+    );
+    assert_eq!(
+        m.compute_step_map(Connectivity::Four)
+            .get(Point { x: 0, y: 0 }),
+        Some(14)
+    );
+}
+
+/// 既知の壁がある場合に、歩数マップを下ってゴールにたどり着くか
+/// 探索しきった後のmin_costとも一致するはず
+#[test]
+pub fn step_map_planner() {
+    let mut m = search_wall_maze::<SearchInfoHeap<16, 16>>();
+    let eight = m.compute_step_map(Connectivity::Eight);
+    let four = m.compute_step_map(Connectivity::Four);
+
+    assert_eq!(eight.get(m.start), m.min_cost);
+    assert_eq!(four.get(m.start), Some(45));
+    // x=7の壁の向こう側には上端を回らないと行けない
+    assert_eq!(four.get(Point { x: 7, y: 0 }), Some(38));
+
+    let mut p = m.start;
+    let mut count = 0;
+    while let Some(next_p) = m.get_step_next(&eight, p) {
+        assert_eq!(eight.get(next_p).unwrap() + 1, eight.get(p).unwrap());
+        p = next_p;
+        count += 1;
+    }
+    assert!(m.goal.contains(p));
+    assert_eq!(Some(count), m.min_cost);
+    assert_eq!(m.trace_answer::<256>().unwrap().len(), count + 1);
+}
+
+/// 右上隅から下向きに開始した場合に、開始位置から探索を始めるか
+#[test]
+pub fn start_from_corner() {
+    const W: usize = 16;
+    const H: usize = 16;
+    let mut m = Explorer::<W, H>::with_start(
+        Point { x: 0, y: 0 },
+        Point { x: W - 1, y: H - 1 },
+        Direction::Down,
+    );
+    assert_eq!(m.cells[H - 1][W - 1].cost, 0);
+    assert!(!m.cells[0][0].flag.contains(CellFlag::IS_COST_AVAILABLE));
+
+    update_all_walls(&mut m, |_, _| false);
+    // 最初は開始位置が返る
+    let mut p = m.get_next().unwrap();
+    assert_eq!(p, m.start);
+    loop {
+        m.fetch_targets(p);
+        match m.get_next() {
+            Some(next_p) => p = next_p,
+            None => break,
+        }
+    }
+    m.debug_print("test.log", "start_from_corner").unwrap();
+
+    assert_eq!(m.min_cost, Some(W - 1));
+    let path = m.trace_answer::<W>().unwrap();
+    assert_eq!(path[0], m.start);
+    assert_eq!(path[W - 1], Point { x: 0, y: 0 });
+
+    // 迷路外の開始位置はエラー
+    let start = Point { x: W, y: 0 };
+    assert_eq!(
+        Explorer::<W, H>::try_with_start(Point { x: 0, y: 0 }, start, Direction::Up).err(),
+        Some(ExplorerError::OutOfBounds(start))
+    );
+}
+
+/// 不正な操作をResultで検出できるか
+#[test]
+pub fn checked_errors() {
+    let mut m = Explorer::<4, 4, SearchInfoProvider<4, 4>>::new(Point { x: 3, y: 3 });
+
+    let mut info = UpdateInfo::default();
+    info.p = Point { x: 4, y: 0 };
+    assert_eq!(
+        m.try_update(&info),
+        Err(ExplorerError::OutOfBounds(Point { x: 4, y: 0 }))
+    );
+    info.p = Point { x: 0, y: 0 };
+    info.up = Some(false);
+    info.right = Some(false);
+    assert_eq!(m.try_update(&info).map(|c| c.len()), Ok(0));
+    // 同じ内容の再観測は食い違いなし
+    assert_eq!(m.try_update(&info).map(|c| c.len()), Ok(0));
+    assert_eq!(
+        m.try_fetch_targets(Point { x: 1, y: 1 }),
+        Err(ExplorerError::CostUnavailable(Point { x: 1, y: 1 }))
+    );
+    assert_eq!(
+        m.try_fetch_targets(Point { x: 0, y: 4 }),
+        Err(ExplorerError::OutOfBounds(Point { x: 0, y: 4 }))
+    );
+
+    // 探索対象を積みきれない場合は何も変更しない
+    while m.provider.get_free() > 0 {
+        assert_eq!(m.provider.try_push(Point { x: 3, y: 3 }, 0), Ok(()));
+    }
+    assert_eq!(
+        m.provider.try_push(Point { x: 3, y: 3 }, 0),
+        Err(ExplorerError::FrontierFull)
+    );
+    assert_eq!(
+        m.try_fetch_targets(Point { x: 0, y: 0 }),
+        Err(ExplorerError::FrontierFull)
+    );
+    assert!(!m.cells[0][0].flag.contains(CellFlag::IS_SEARCH_AROUND));
+    assert!(!m.cells[1][0].flag.contains(CellFlag::IS_COST_AVAILABLE));
+
+    m.provider.clear();
+    assert_eq!(m.try_fetch_targets(Point { x: 0, y: 0 }), Ok(()));
+    assert_eq!(m.provider.get_count(), 2);
+}
+
+/// 旋回コストを考慮した経路
+#[test]
+pub fn plan_with_heading() {
+    const W: usize = 16;
+    const H: usize = 16;
+    let mut m = Explorer::<W, H>::new(Point { x: W - 1, y: H - 1 });
+    update_all_walls(&mut m, |_, _| false);
+    m.step_cost = StepCost::new(10, 14).unwrap();
+    let mut work = HeadingPlanWork::<W, H>::default();
+
+    // 旋回コストがなければ斜めに直行する
+    let plan = m
+        .plan_with_heading::<256>(&TurnCost::default(), &mut work)
+        .unwrap();
+    assert_eq!(plan.cost, 15 * 14);
+    assert_eq!(plan.path.len(), 16);
+
+    // 45度旋回が重ければ、上端まで直進してから90度曲がる
+    let plan = m
+        .plan_with_heading::<256>(&TurnCost::new(100, 5, 200, 300), &mut work)
+        .unwrap();
+    assert_eq!(plan.cost, 15 * 10 + 5 + 15 * 10);
+    assert_eq!(plan.path.len(), 31);
+    assert_eq!(plan.path[0], Point { x: 0, y: 0 });
+    assert_eq!(plan.path[15], Point { x: 0, y: 15 });
+    assert_eq!(plan.path[30], Point { x: 15, y: 15 });
+
+    // 開始時の向きも旋回として数える
+    m.start_heading = Direction::Right;
+    let plan = m
+        .plan_with_heading::<256>(&TurnCost::new(100, 5, 200, 300), &mut work)
+        .unwrap();
+    assert_eq!(plan.cost, 15 * 10 + 5 + 15 * 10);
+    assert_eq!(plan.path[15], Point { x: 15, y: 0 });
+
+    // 壁があっても、旋回コストがなければ探索しきった最短と一致する
+    let m = search_wall_maze::<SearchInfoHeap<W, H>>();
+    let plan = m
+        .plan_with_heading::<256>(&TurnCost::default(), &mut work)
+        .unwrap();
+    assert_eq!(Some(plan.cost), m.min_cost);
+    assert_eq!(plan.path.len(), plan.cost + 1);
+
+    // 後ろ向きに出るより、先のループを回ってstartを逆向きに通り直す方が安い
+    let mut m =
+        Explorer::<W, H>::with_start(Point { x: 5, y: 0 }, Point { x: 5, y: 5 }, Direction::Up);
+    update_all_walls(&mut m, |p, dir| match dir {
+        Direction::Up => !(p.x == 5 && p.y < 7 || p == Point { x: 6, y: 6 }),
+        _ => !(p.x == 5 && p.y >= 6),
+    });
+    m.step_cost = StepCost::new(10, 14).unwrap();
+    let plan = m
+        .plan_with_heading::<256>(&TurnCost::new(1000, 5, 1000, 1000), &mut work)
+        .unwrap();
+    assert_eq!(plan.cost, 11 * 10 + 4 * 5);
+    assert_eq!(plan.path[0], Point { x: 5, y: 5 });
+    assert_eq!(plan.path[6], Point { x: 5, y: 5 });
+    assert_eq!(plan.path[11], Point { x: 5, y: 0 });
+}
+
+/// 壁のない迷路
+fn open_maze() -> Explorer<16, 16> {
+    let mut m = Explorer::<16, 16>::new(Point { x: 15, y: 15 });
+    update_all_walls(&mut m, |_, _| false);
+    m
+}
+
+fn points(xy: &[(usize, usize)]) -> Vec<Point> {
+    xy.iter().map(|&(x, y)| Point { x, y }).collect()
+}
+
+/// 経路を動作列に変換する
+#[test]
+pub fn compile_motion() {
+    let m = open_maze();
+    let compile =
+        |xy: &[(usize, usize)], mode| m.compile_motion::<32>(&points(xy), Direction::Up, mode);
+
+    let straight = compile(&[(0, 0), (0, 1), (0, 2), (0, 3)], MotionMode::Diagonal).unwrap();
+    assert_eq!(&straight[..], &[Motion::Straight(6)]);
+
+    let turn = [(0, 0), (0, 1), (0, 2), (1, 2), (2, 2)];
+    let search = compile(&turn, MotionMode::Search).unwrap();
+    assert_eq!(
+        &search[..],
+        &[
+            Motion::Straight(3),
+            Motion::SearchTurn90(Turn::Right),
+            Motion::Straight(3)
+        ]
+    );
+    let slalom = compile(&turn, MotionMode::Slalom).unwrap();
+    assert_eq!(
+        &slalom[..],
+        &[
+            Motion::Straight(2),
+            Motion::Turn90(Turn::Right),
+            Motion::Straight(2)
+        ]
+    );
+
+    // 斜めの移動は左右交互に曲がる迂回ルートに展開される
+    let diagonal = compile(
+        &[(0, 0), (0, 1), (1, 2), (2, 3), (2, 4)],
+        MotionMode::Diagonal,
+    )
+    .unwrap();
+    assert_eq!(
+        &diagonal[..],
+        &[
+            Motion::Turn45In(Turn::Right),
+            Motion::DiagonalStraight(2),
+            Motion::Turn45Out(Turn::Left),
+            Motion::Straight(2)
+        ]
+    );
+
+    let v90 = [
+        (0, 0),
+        (0, 1),
+        (1, 1),
+        (1, 2),
+        (2, 2),
+        (2, 1),
+        (3, 1),
+        (3, 0),
+    ];
+    let v90 = compile(&v90, MotionMode::Diagonal).unwrap();
+    assert_eq!(
+        &v90[..],
+        &[
+            Motion::Turn45In(Turn::Right),
+            Motion::DiagonalStraight(1),
+            Motion::TurnV90(Turn::Right),
+            Motion::DiagonalStraight(1),
+            Motion::Turn45Out(Turn::Right)
+        ]
+    );
+
+    let u_turn = compile(&[(0, 0), (0, 1), (1, 1), (1, 0)], MotionMode::Diagonal).unwrap();
+    assert_eq!(&u_turn[..], &[Motion::Turn180(Turn::Right)]);
+}
+
+/// 動作列に変換できない経路
+#[test]
+pub fn compile_motion_errors() {
+    let mut m = open_maze();
+    let compile = |m: &Explorer<16, 16>, xy: &[(usize, usize)], mode| {
+        m.compile_motion::<32>(&points(xy), Direction::Up, mode)
+    };
+
+    assert_eq!(
+        compile(&m, &[(0, 0), (0, 2)], MotionMode::Search),
+        Err(MotionError::NotAdjacent(Point { x: 0, y: 2 }))
+    );
+    assert_eq!(
+        compile(&m, &[(0, 0), (0, 1), (0, 0)], MotionMode::Search),
+        Err(MotionError::Reversal(Point { x: 0, y: 1 }))
+    );
+    // 同じ向きに3回曲がる
+    let spiral = [(0, 0), (0, 1), (1, 1), (1, 0), (0, 0)];
+    assert!(compile(&m, &spiral, MotionMode::Search).is_ok());
+    assert_eq!(
+        compile(&m, &spiral, MotionMode::Diagonal),
+        Err(MotionError::Unrealizable(Point { x: 0, y: 1 }))
+    );
+    assert_eq!(
+        m.compile_motion::<0>(
+            &points(&[(0, 0), (0, 1)]),
+            Direction::Up,
+            MotionMode::Search
+        ),
+        Err(MotionError::CapacityExceeded)
+    );
+
+    // 開始時の向きと最初の移動方向が違うとその場で旋回する必要がある
+    let up = points(&[(0, 0), (0, 1)]);
+    assert_eq!(
+        m.compile_motion::<32>(&up, Direction::Right, MotionMode::Search),
+        Err(MotionError::Unrealizable(Point { x: 0, y: 0 }))
+    );
+    assert!(m
+        .compile_motion::<32>(&up, Direction::NoDir, MotionMode::Search)
+        .is_ok());
+    // 斜めで始まる場合は開始時の向きに進む迂回ルートを選ぶ
+    let diagonal = points(&[(0, 0), (1, 1), (2, 1)]);
+    let motions = m
+        .compile_motion::<32>(&diagonal, Direction::Right, MotionMode::Search)
+        .unwrap();
+    assert_eq!(
+        &motions[..],
+        &[
+            Motion::Straight(1),
+            Motion::SearchTurn90(Turn::Left),
+            Motion::SearchTurn90(Turn::Right),
+            Motion::Straight(1)
+        ]
+    );
+
+    // 迂回ルートが両方ふさがっている斜め
+    let mut info = UpdateInfo::default();
+    info.p = Point { x: 5, y: 5 };
+    info.up = Some(true);
+    info.right = Some(true);
+    m.update(&info);
+    assert_eq!(
+        compile(&m, &[(5, 5), (6, 6)], MotionMode::Diagonal),
+        Err(MotionError::Blocked(Point { x: 5, y: 5 }))
+    );
+    assert_eq!(
+        compile(&m, &[(5, 4), (5, 5), (5, 6)], MotionMode::Diagonal),
+        Err(MotionError::Blocked(Point { x: 5, y: 5 }))
+    );
+}
+
+/// 台形加減速での走行時間の見積もり
+#[test]
+pub fn estimate_run_time() {
+    let profile = VelocityProfile {
+        max_speed: 1000,
+        acceleration: 1000,
+        deceleration: 1000,
+        turn90: TurnProfile::new(600, 300_000),
+        ..VelocityProfile::default()
+    };
+    // 最高速度に届かない
+    assert_eq!(profile.estimate(&[Motion::Straight(4)]), 1_200_000);
+    // 加速、等速、減速
+    assert_eq!(profile.estimate(&[Motion::Straight(32)]), 3_880_000);
+    // ターンの速度で出入りする
+    assert_eq!(
+        profile.estimate(&[
+            Motion::Straight(2),
+            Motion::Turn90(Turn::Right),
+            Motion::Straight(2)
+        ]),
+        1_500_000
+    );
+    assert_eq!(
+        profile.distance(&Motion::DiagonalStraight(2)),
+        254_558 // 180mm * √2
+    );
+
+    let m = open_maze();
+    let path = points(&[(0, 0), (0, 1), (0, 2)]);
+    assert_eq!(
+        m.estimate_run_time::<8>(&path, Direction::Up, MotionMode::Diagonal, &profile),
+        Ok(1_200_000)
+    );
+}
+
+/// 壁の状態の取得と設定
+#[test]
+pub fn wall_state() {
+    let mut m: Explorer<4, 4> = Explorer::new(Point { x: 3, y: 3 });
+    let p = Point { x: 1, y: 1 };
+    assert_eq!(m.wall(p, Direction::Up), WallState::Unknown);
+    assert_eq!(
+        m.wall(Point { x: 0, y: 0 }, Direction::Down),
+        WallState::Present
+    );
+    assert_eq!(
+        m.wall(Point { x: 0, y: 2 }, Direction::Left),
+        WallState::Present
+    );
+    assert_eq!(
+        m.wall(Point { x: 3, y: 3 }, Direction::Up),
+        WallState::Present
+    );
+
+    // 隣の区画から見ても同じ壁
+    m.set_wall(p, Direction::Right, WallState::Present);
+    assert_eq!(
+        m.wall(Point { x: 2, y: 1 }, Direction::Left),
+        WallState::Present
+    );
+    m.set_wall(Point { x: 1, y: 2 }, Direction::Down, WallState::Absent);
+    assert_eq!(m.wall(p, Direction::Up), WallState::Absent);
+    m.set_wall(p, Direction::Up, WallState::Unknown);
+    assert_eq!(
+        m.wall(Point { x: 1, y: 2 }, Direction::Down),
+        WallState::Unknown
+    );
+
+    // 外周は変更できない
+    m.set_wall(Point { x: 3, y: 0 }, Direction::Right, WallState::Absent);
+    assert_eq!(
+        m.wall(Point { x: 3, y: 0 }, Direction::Right),
+        WallState::Present
+    );
+}
+
+/// 隣接区画が迷路外ならNoneになるか
+#[test]
+pub fn point_neighbor() {
+    // 迷路の外に出る方向はNone
+    let corner = Point { x: 0, y: 0 };
+    assert_eq!(corner.neighbor::<4, 4>(Direction::Left), None);
+    assert_eq!(corner.neighbor::<4, 4>(Direction::Down), None);
+    assert_eq!(corner.neighbor::<4, 4>(Direction::DownRight), None);
+    assert_eq!(
+        corner.neighbor::<4, 4>(Direction::UpRight),
+        Some(Point { x: 1, y: 1 })
+    );
+    let edge = Point { x: 3, y: 3 };
+    assert_eq!(edge.neighbor::<4, 4>(Direction::Up), None);
+    assert_eq!(edge.neighbor::<4, 4>(Direction::Right), None);
+    assert_eq!(
+        edge.neighbor::<4, 4>(Direction::Left),
+        Some(Point { x: 2, y: 3 })
+    );
+    assert_eq!(edge.neighbor::<4, 4>(Direction::NoDir), Some(edge));
+    assert_eq!(Point { x: 4, y: 0 }.neighbor::<4, 4>(Direction::Left), None);
+
+    // 隅の区画は上下左右で2、斜め込みで3
+    assert_eq!(corner.neighbors::<4, 4>(false).count(), 2);
+    assert_eq!(
+        corner.neighbors::<4, 4>(true).collect::<Vec<_>>(),
+        vec![
+            (Direction::Up, Point { x: 0, y: 1 }),
+            (Direction::Right, Point { x: 1, y: 0 }),
+            (Direction::UpRight, Point { x: 1, y: 1 }),
+        ]
+    );
+    assert_eq!(Point { x: 1, y: 1 }.neighbors::<4, 4>(true).count(), 8);
+}
+
+/// 向きの回転、反転と変化量の相互変換
+#[test]
+pub fn direction_rotation() {
+    assert_eq!(Direction::Up.rotate_cw45(), Direction::UpRight);
+    assert_eq!(Direction::Up.rotate_ccw45(), Direction::UpLeft);
+    assert_eq!(Direction::Left.rotate_cw90(), Direction::Up);
+    assert_eq!(Direction::Left.rotate_ccw90(), Direction::Down);
+    assert_eq!(Direction::DownLeft.opposite(), Direction::UpRight);
+    assert_eq!(Direction::NoDir.rotate_cw90(), Direction::NoDir);
+    for dir in Direction::ALL8 {
+        assert_eq!(dir.rotate_cw90().rotate_ccw90(), dir);
+        assert_eq!(dir.opposite().opposite(), dir);
+        let (dx, dy) = dir.delta();
+        assert_eq!(Direction::from_delta(dx, dy), dir);
+        assert_eq!(dir.is_diagonal(), dx != 0 && dy != 0);
+    }
+    assert!(Direction::ALL4.iter().all(|d| !d.is_diagonal()));
+    assert_eq!(Direction::from_delta(2, 0), Direction::NoDir);
+    assert_eq!(
+        Point { x: 1, y: 1 }.direction_to(Point { x: 0, y: 2 }),
+        Direction::UpLeft
+    );
+}
+
+/// 向きを変えるときの角度。時計回りが正
+#[test]
+pub fn direction_turn_angle() {
+    assert_eq!(Direction::Up.turn_angle(Direction::Up), Some(0));
+    assert_eq!(Direction::Up.turn_angle(Direction::Right), Some(90));
+    assert_eq!(Direction::Up.turn_angle(Direction::Left), Some(-90));
+    assert_eq!(Direction::Up.turn_angle(Direction::DownRight), Some(135));
+    assert_eq!(Direction::Up.turn_angle(Direction::DownLeft), Some(-135));
+    assert_eq!(Direction::Left.turn_angle(Direction::Right), Some(180));
+    assert_eq!(Direction::UpLeft.turn_angle(Direction::UpRight), Some(90));
+    assert_eq!(Direction::NoDir.turn_angle(Direction::Up), None);
+}
+
+/// 機体から見た壁を、向きに合わせて上下左右の壁に変換できるか
+#[test]
+pub fn observation_to_update_info() {
+    let p = Point { x: 1, y: 1 };
+    let mut obs = Observation::new(p, Direction::Right);
+    obs.front = Some(true);
+    obs.left = Some(false);
+    obs.right = Some(true);
+
+    // 右向きなら前が右、左が上、右が下
+    let info = obs.to_update_info().unwrap();
+    assert_eq!(info.p, p);
+    assert_eq!(info.right, Some(true));
+    assert_eq!(info.up, Some(false));
+    assert_eq!(info.down, Some(true));
+    assert_eq!(info.left, None);
+
+    // 下向きなら前が下、左が右、右が左、後ろが上
+    obs.heading = Direction::Down;
+    obs.back = Some(false);
+    let info = obs.to_update_info().unwrap();
+    assert_eq!(info.down, Some(true));
+    assert_eq!(info.right, Some(false));
+    assert_eq!(info.left, Some(true));
+    assert_eq!(info.up, Some(false));
+    assert_eq!(obs.wall(Direction::DownLeft), None);
+
+    obs.heading = Direction::UpRight;
+    assert_eq!(
+        obs.to_update_info().unwrap_err(),
+        ExplorerError::InvalidHeading(Direction::UpRight)
+    );
+}
+
+/// 機体から見た壁で壁情報を更新する
+#[test]
+pub fn explorer_observe() {
+    let mut m = Explorer::<4, 4>::default();
+    let p = Point { x: 0, y: 1 };
+    let mut obs = Observation::new(p, Direction::Up);
+    obs.front = Some(false);
+    obs.right = Some(true);
+    m.observe(&obs).unwrap();
+    assert_eq!(m.wall(p, Direction::Up), WallState::Absent);
+    assert_eq!(m.wall(p, Direction::Right), WallState::Present);
+    assert_eq!(m.wall(p, Direction::Down), WallState::Unknown);
+
+    obs.heading = Direction::NoDir;
+    assert_eq!(
+        m.observe(&obs),
+        Err(ExplorerError::InvalidHeading(Direction::NoDir))
+    );
+}
+
+/// 再観測で壁が食い違った場合
+#[test]
+pub fn reobserve_last_wins() {
+    let mut m = Explorer::<4, 4>::new(Point { x: 3, y: 3 });
+    update_all_walls(&mut m, |_, _| false);
+    let p = Point { x: 0, y: 0 };
+    let mut info = UpdateInfo::default();
+    info.p = p;
+    info.right = Some(true);
+    let conflicts = m.try_update(&info).unwrap();
+    assert!(conflicts[0].is_flipped());
+    while let Some(p) = m.get_next() {
+        m.fetch_targets(p);
+    }
+    assert!(m.min_cost.is_some());
+    assert!(m.cells[1][0].flag.contains(CellFlag::IS_COST_AVAILABLE));
+
+    // 誤読だった右の壁が実はなかった
+    info.right = Some(false);
+    let conflicts = m.try_update(&info).unwrap();
+    assert_eq!(
+        conflicts.as_slice(),
+        &[WallConflict {
+            p,
+            dir: Direction::Right,
+            previous: WallState::Present,
+            current: WallState::Absent,
+        }]
+    );
+    assert!(conflicts[0].is_flipped());
+    assert_eq!(m.wall(p, Direction::Right), WallState::Absent);
+    assert_eq!(
+        m.wall_votes(p, Direction::Right),
+        Some(WallVotes::new(1, 2))
+    );
+
+    // 壁がなくなっただけなので、既存のコストは残して開始位置から展開し直す
+    assert_eq!(m.min_cost, Some(3));
+    assert!(m.cells[1][0].flag.contains(CellFlag::IS_COST_AVAILABLE));
+    assert!(m.cells[1][0].flag.contains(CellFlag::IS_UPDATED));
+    assert_eq!(m.cells[0][1].cost, 1);
+    assert_eq!(
+        m.wall(Point { x: 0, y: 1 }, Direction::Up),
+        WallState::Absent
+    );
+    while let Some(p) = m.get_next() {
+        m.fetch_targets(p);
+    }
+    assert_eq!(m.min_cost, Some(3));
+}
+
+/// 壁が増えた場合は、その壁を通っていた区画のコストだけを捨てる
+#[test]
+pub fn reobserve_invalidate() {
+    const W: usize = 8;
+    const H: usize = 8;
+    let goal = Point { x: W - 1, y: H - 1 };
+    let mut m = Explorer::<W, H>::new(goal);
+    update_all_walls(&mut m, |_, _| false);
+    while let Some(p) = m.get_next() {
+        m.fetch_targets(p);
+    }
+    assert_eq!(m.min_cost, Some(7));
+    m.trace_answer::<64>().unwrap();
+
+    // x=3の右側に上端以外をふさぐ壁が見つかった
+    let is_wall = |p: Point, dir| dir == Direction::Right && p.x == 3 && p.y < H - 1;
+    for y in 0..H - 1 {
+        let mut info = UpdateInfo::default();
+        info.p = Point { x: 3, y };
+        info.right = Some(true);
+        m.try_update(&info).unwrap();
+    }
+    // 壁の手前はそのまま、向こう側は回り込まないと行けないので捨てられている
+    assert_eq!(m.cells[1][0].cost, 1);
+    assert!(m.cells[3][3].flag.contains(CellFlag::IS_COST_AVAILABLE));
+    assert!(!m.cells[4][4].flag.contains(CellFlag::IS_COST_AVAILABLE));
+    assert!(!m.cells[H - 1][W - 1].flag.contains(CellFlag::IS_ANSWER));
+    assert_eq!(m.min_cost, None);
+
+    // 探索し直すと、最初から壁を知っていた場合と同じ最短になる
+    while let Some(p) = m.get_next() {
+        m.fetch_targets(p);
+    }
+    let mut answer = Explorer::<W, H>::new(goal);
+    update_all_walls(&mut answer, is_wall);
+    while let Some(p) = answer.get_next() {
+        answer.fetch_targets(p);
+    }
+    assert_eq!(m.min_cost, answer.min_cost);
+    let path = m.trace_answer::<64>().unwrap();
+    assert_eq!(path.len(), m.min_cost.unwrap() + 1);
+}
+
+/// 多数決では、観測回数の多い方に壁が決まるか
+#[test]
+pub fn reobserve_majority() {
+    let mut m = Explorer::<4, 4>::new(Point { x: 3, y: 3 });
+    m.wall_policy = WallPolicy::Majority;
+    let p = Point { x: 1, y: 1 };
+    let mut info = UpdateInfo::default();
+    info.p = p;
+    info.up = Some(true);
+    m.update(&info);
+    m.update(&info);
+
+    // 1回だけの食い違いは報告するが、多数決で壁は残る
+    info.up = Some(false);
+    let conflicts = m.try_update(&info).unwrap();
+    assert_eq!(conflicts.len(), 1);
+    assert!(!conflicts[0].is_flipped());
+    assert_eq!(m.wall(p, Direction::Up), WallState::Present);
+    assert!(m.cells[0][0].flag.contains(CellFlag::IS_COST_AVAILABLE));
+
+    // 同数では現状維持、上回ったら反転する。上の区画から見た下の壁も同じ壁
+    let mut above = UpdateInfo::default();
+    above.p = Point { x: 1, y: 2 };
+    above.down = Some(false);
+    assert!(!m.try_update(&above).unwrap()[0].is_flipped());
+    assert_eq!(m.wall(p, Direction::Up), WallState::Present);
+    let conflicts = m.try_update(&above).unwrap();
+    assert_eq!(conflicts[0].current, WallState::Absent);
+    assert!(conflicts[0].is_flipped());
+    assert_eq!(m.wall(p, Direction::Up), WallState::Absent);
+
+    // 回数は4bitに詰めているので、上限に達したら反対側を減らして数え続ける
+    let mut votes = WallVotes::default();
+    for _ in 0..20 {
+        votes.add(true);
+    }
+    assert_eq!(votes, WallVotes::new(WallVotes::MAX, 0));
+    assert_eq!(votes.majority(), WallState::Present);
+    for _ in 0..20 {
+        votes.add(false);
+    }
+    assert_eq!(votes, WallVotes::new(10, WallVotes::MAX));
+    assert_eq!(votes.majority(), WallState::Absent);
+
+    // 上限の境目でも、1回の観測で多い方が入れ替わるのは差が1のときだけ
+    let mut votes = WallVotes::new(15, 14);
+    votes.add(false);
+    assert_eq!(votes.majority(), WallState::Unknown);
+    votes.add(false);
+    assert_eq!(votes, WallVotes::new(14, 15));
+    assert_eq!(votes.majority(), WallState::Absent);
+    let mut votes = WallVotes::new(15, 3);
+    votes.add(true);
+    assert_eq!(votes, WallVotes::new(15, 2));
+}
+
+/// 多数決で壁がなくなった場合も、探索し直すと最初から知っていた場合と同じ最短になるか
+#[test]
+pub fn reobserve_majority_opened() {
+    const W: usize = 16;
+    const H: usize = 16;
+    let goal = Point { x: W - 1, y: 0 };
+    let mut m = Explorer::<W, H>::new(goal);
+    m.wall_policy = WallPolicy::Majority;
+    // x=7の右側に上端以外をふさぐ壁
+    update_all_walls(&mut m, |p, dir| {
+        dir == Direction::Right && p.x == 7 && p.y < H - 1
+    });
+    while let Some(p) = m.get_next() {
+        m.fetch_targets(p);
+    }
+    assert_eq!(m.min_cost, Some(30));
+
+    // 下端の壁がないと2回続けて観測すると、多数決で取り除かれる
+    let mut info = UpdateInfo::default();
+    info.p = Point { x: 8, y: 0 };
+    info.left = Some(false);
+    assert!(!m.try_update(&info).unwrap()[0].is_flipped());
+    assert!(m.try_update(&info).unwrap()[0].is_flipped());
+    while let Some(p) = m.get_next() {
+        m.fetch_targets(p);
+    }
+
+    let mut answer = Explorer::<W, H>::new(goal);
+    update_all_walls(&mut answer, |p, dir| {
+        dir == Direction::Right && p.x == 7 && 0 < p.y && p.y < H - 1
+    });
+    while let Some(p) = answer.get_next() {
+        answer.fetch_targets(p);
+    }
+    assert_eq!(answer.min_cost, Some(15));
+    assert_eq!(m.min_cost, answer.min_cost);
+}
@@ -0,0 +1,376 @@
+#![allow(
+    clippy::field_reassign_with_default,
+    clippy::bool_assert_comparison,
+    clippy::doc_lazy_continuation
+)]
+extern crate oratosquilla;
+
+use oratosquilla::prelude::*;
+
+/// x=7の右側に上端以外をふさぐ壁がある正解迷路
+fn wall_maze() -> Maze<16, 16> {
+    let mut maze = Maze::<16, 16>::new(Point { x: 0, y: 0 }, Point { x: 15, y: 0 });
+    for y in 0..15 {
+        maze.set_wall(Point { x: 7, y }, Direction::Right, true);
+    }
+    maze
+}
+
+/// 正解迷路の壁が隣の区画と共有されているか
+#[test]
+pub fn maze_walls() {
+    let mut maze = wall_maze();
+
+    assert!(maze.has_wall(Point { x: 8, y: 3 }, Direction::Left));
+    assert!(!maze.has_wall(Point { x: 8, y: 15 }, Direction::Left));
+    assert!(maze.has_wall(Point { x: 0, y: 0 }, Direction::Down));
+    assert!(maze.has_wall(Point { x: 15, y: 15 }, Direction::Up));
+    // 外周は消せない
+    maze.set_wall(Point { x: 0, y: 3 }, Direction::Left, false);
+    assert!(maze.has_wall(Point { x: 0, y: 3 }, Direction::Left));
+    maze.set_wall(Point { x: 3, y: 4 }, Direction::Down, true);
+    assert!(maze.has_wall(Point { x: 3, y: 3 }, Direction::Up));
+
+    let info = maze.get_update_info(Point { x: 3, y: 3 });
+    assert_eq!(info.up, Some(true));
+    assert_eq!(info.down, Some(false));
+    assert_eq!(info.left, Some(false));
+    assert_eq!(info.right, Some(false));
+}
+
+/// 正解迷路を既知としたExplorerで探索しきれば最短になるか
+#[test]
+pub fn explorer_from_maze() {
+    let mut m = Explorer::<16, 16>::from_maze(&wall_maze());
+    let mut p = m.get_next().unwrap();
+    loop {
+        m.fetch_targets(p);
+        match m.get_next() {
+            Some(next_p) => p = next_p,
+            None => break,
+        }
+    }
+    assert_eq!(m.min_cost, Some(30));
+}
+
+/// 斜めの移動コストを変えて探索する
+#[test]
+pub fn explorer_step_cost() {
+    let step_cost = StepCost::new(10, 14).unwrap();
+    // 斜めが直進より安い、もしくは直進2回より高いと推定値が実際を超える
+    assert_eq!(StepCost::new(10, 9), None);
+    assert_eq!(StepCost::new(10, 21), None);
+    assert_eq!(
+        step_cost.distance(Point { x: 0, y: 0 }, Point { x: 7, y: 15 }),
+        7 * 14 + 8 * 10
+    );
+
+    let mut m = Explorer::<16, 16>::from_maze(&wall_maze());
+    m.step_cost = step_cost;
+    while let Some(p) = m.get_next() {
+        m.fetch_targets(p);
+    }
+    // 上端で壁を回り込む。(0,0)-(7,15)-(15,0)の斜め優先経路と同じ
+    assert_eq!(m.min_cost, Some(360));
+    let path = m.trace_answer::<256>().unwrap();
+    let cost: usize = path.windows(2).map(|w| step_cost.get(w[0], w[1])).sum();
+    assert_eq!(cost, 360);
+}
+
+/// シミュレータで壁を観測しながらゴールまで探索できるか
+#[test]
+pub fn simulate_wall_maze() {
+    let mut sim = Simulator::<16, 16>::new(wall_maze());
+    let report = sim.run().unwrap();
+
+    assert!(report.goal_steps.is_some());
+    assert_eq!(report.goal_steps, Some(report.steps));
+    assert!(sim.explorer.goal.contains(sim.position));
+    assert!(report.min_cost.unwrap() >= 30);
+
+    // 最後まで探索すると最短が確定する
+    let report = sim.run_to_end().unwrap();
+    sim.explorer
+        .debug_print("test.log", "simulate_wall_maze")
+        .unwrap();
+    assert!(sim.step().unwrap().is_none());
+    assert!(report.steps > report.goal_steps.unwrap());
+    assert!(report.distance >= report.steps - 1);
+    assert_eq!(report.min_cost, Some(30));
+    let path = sim.explorer.trace_answer::<256>().unwrap();
+    assert_eq!(path.len(), 31);
+    for p in &path {
+        assert!(sim.explorer.cells[p.y][p.x]
+            .flag
+            .contains(CellFlag::IS_UPDATED));
+    }
+}
+
+/// 生成した迷路でも、最後まで探索すれば正解迷路を既知とした最短と一致するか
+#[test]
+pub fn simulate_generated() {
+    for seed in 0..10 {
+        for loops in [0, 10, 40] {
+            let maze = Maze::<16, 16>::generate(seed, loops);
+            let mut answer = Explorer::<16, 16>::from_maze(&maze);
+            while let Some(p) = answer.get_next() {
+                answer.fetch_targets(p);
+            }
+            let mut sim = Simulator::<16, 16>::new(maze);
+            let report = sim.run_to_end().unwrap();
+            assert!(report.min_cost.is_some());
+            assert_eq!(
+                report.min_cost, answer.min_cost,
+                "seed {} loops {}",
+                seed, loops
+            );
+        }
+    }
+}
+
+/// シミュレータの移動距離が壁を通り抜けずに数えられ、通った区画の壁を観測しているか
+#[test]
+pub fn simulate_distance() {
+    let maze = wall_maze();
+    let answer = Explorer::<16, 16>::from_maze(&maze);
+    let mut sim = Simulator::<16, 16>::new(maze);
+    let mut is_detoured = false;
+    loop {
+        let from = sim.position;
+        let distance = sim.report.distance;
+        // 移動前の壁情報で、通る経路を後から求める
+        let mut before = Explorer::<16, 16>::new(answer.goal.clone());
+        before.cells = sim.explorer.cells;
+        let target = match sim.step().unwrap() {
+            Some(p) => p,
+            None => break,
+        };
+        // 通ってきた区画の壁は観測済になっている
+        let step_map = before.compute_step_map_to(target, Connectivity::Four);
+        let mut p = from;
+        while let Some(next) = before.get_step_next(&step_map, p) {
+            assert!(sim.explorer.cells[next.y][next.x]
+                .flag
+                .contains(CellFlag::IS_UPDATED));
+            p = next;
+        }
+        // 正解迷路での最短歩数より短く移動することはない
+        let moved = sim.report.distance - distance;
+        let shortest = answer
+            .compute_step_map_to(target, Connectivity::Four)
+            .get(from)
+            .unwrap();
+        assert!(moved >= shortest);
+        is_detoured |= moved > from.distance(target);
+        assert_eq!(sim.position, target);
+    }
+    // x=7の壁をまたぐ目標は回り込んで移動している
+    assert!(is_detoured);
+}
+
+/// mazefiles形式の読み込み
+#[test]
+pub fn mazefile_parse() {
+    let text = "
+o---o---o---o
+| G   G     |
+o   o---o   o
+|   |       |
+o   o   o---o
+| S |
+o---o---o---o
+";
+    assert_eq!(mazefile_size(text), Some((3, 3)));
+    let maze = Maze::<3, 3>::from_mazefile(text).unwrap();
+    assert_eq!(maze.start, Point { x: 0, y: 0 });
+    assert!(maze.goal.contains(Point { x: 0, y: 2 }));
+    assert!(maze.goal.contains(Point { x: 1, y: 2 }));
+    assert!(!maze.goal.contains(Point { x: 2, y: 2 }));
+    assert!(maze.has_wall(Point { x: 0, y: 0 }, Direction::Right));
+    assert!(!maze.has_wall(Point { x: 0, y: 0 }, Direction::Up));
+    assert!(maze.has_wall(Point { x: 1, y: 1 }, Direction::Up));
+    assert!(maze.has_wall(Point { x: 2, y: 1 }, Direction::Down));
+    assert!(!maze.has_wall(Point { x: 1, y: 0 }, Direction::Right));
+
+    let mut m = Explorer::<3, 3>::from_maze(&maze);
+    let mut p = m.get_next().unwrap();
+    loop {
+        m.fetch_targets(p);
+        match m.get_next() {
+            Some(next_p) => p = next_p,
+            None => break,
+        }
+    }
+    assert_eq!(m.min_cost, Some(2));
+}
+
+/// mazefiles形式の読み込みエラー
+#[test]
+pub fn mazefile_errors() {
+    let err = Maze::<2, 1>::from_mazefile("o---o---o\n| S   G |\no---o-x-o\n").unwrap_err();
+    assert_eq!(err.line, 3);
+    assert_eq!(err.column, 7);
+    assert_eq!(err.kind, MazefileErrorKind::UnexpectedChar('x'));
+
+    let err = Maze::<2, 2>::from_mazefile("o---o---o\n| S   G |\no---o---o\n").unwrap_err();
+    assert_eq!(err.kind, MazefileErrorKind::SizeMismatch);
+
+    let err = Maze::<2, 1>::from_mazefile("o---o---o\n| S   S |\no---o---o\n").unwrap_err();
+    assert_eq!((err.line, err.column), (2, 7));
+    assert_eq!(err.kind, MazefileErrorKind::MultipleStart);
+}
+
+/// mazefiles形式の書き出し
+#[test]
+pub fn mazefile_write() {
+    let text = "\
+o---o---o---o
+| G   G     |
+o   o---o   o
+|   |       |
+o   o   o---o
+| S |       |
+o---o---o---o
+";
+    let maze = Maze::<3, 3>::from_mazefile(text).unwrap();
+    let mut dst = String::new();
+    maze.write_mazefile(&mut dst).unwrap();
+    assert_eq!(dst, text);
+
+    // 既知の壁だけなら正解と一致する
+    let m = Explorer::<3, 3>::from_maze(&maze);
+    let mut dst = String::new();
+    m.write_mazefile(&mut dst).unwrap();
+    assert_eq!(dst, text);
+
+    // 未確定の壁は?になる
+    let mut m = Explorer::<3, 3>::new(maze.goal.clone());
+    m.update(&maze.get_update_info(Point { x: 0, y: 0 }));
+    let mut dst = String::new();
+    m.write_mazefile(&mut dst).unwrap();
+    let lines = dst.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "o---o---o---o");
+    assert_eq!(lines[1], "| G   G ?   |");
+    assert_eq!(lines[4], "o   o???o???o");
+    assert_eq!(lines[5], "| S |   ?   |");
+
+    // Explorerで読み込めば未確定の壁も含めて元に戻る
+    let read = Explorer::<3, 3>::from_mazefile(&dst).unwrap();
+    for y in 0..3 {
+        for x in 0..3 {
+            let p = Point { x, y };
+            for dir in Direction::ALL4 {
+                assert_eq!(read.wall(p, dir), m.wall(p, dir));
+            }
+        }
+    }
+    let mut reread = String::new();
+    read.write_mazefile(&mut reread).unwrap();
+    assert_eq!(reread, dst);
+    // Mazeで読み込むと未確定の壁は壁ありになる
+    let maze = Maze::<3, 3>::from_mazefile(&dst).unwrap();
+    assert!(maze.has_wall(Point { x: 1, y: 0 }, Direction::Up));
+    assert!(!maze.has_wall(Point { x: 0, y: 0 }, Direction::Up));
+}
+
+/// .mazの読み書き
+#[test]
+pub fn maz_roundtrip() {
+    let maze = wall_maze();
+    let mut data = [0u8; 256];
+    maze.to_maz(&mut data).unwrap();
+    // (7,0)は上と右に壁がある
+    assert_eq!(data[7 * 16], 0x02 | 0x04);
+    assert_eq!(data[8 * 16], 0x04 | 0x08);
+
+    let m = Explorer::<16, 16>::from_maz(&data).unwrap();
+    let mut dst = [0u8; 256];
+    m.to_maz(&mut dst).unwrap();
+    assert_eq!(data[..], dst[..]);
+
+    // 隣り合う区画で食い違っている
+    data[8 * 16] &= !0x08;
+    assert_eq!(
+        Maze::<16, 16>::from_maz(&data).unwrap_err(),
+        MazError::Inconsistent {
+            p: Point { x: 7, y: 0 },
+            dir: Direction::Right
+        }
+    );
+    assert_eq!(
+        Maze::<16, 16>::from_maz(&data[..255]).unwrap_err(),
+        MazError::InvalidLength(255)
+    );
+
+    // 未確定の壁は存在するものとして書き出す
+    let m = Explorer::<16, 16>::new(Point { x: 15, y: 0 });
+    m.to_maz(&mut dst).unwrap();
+    assert!(dst.iter().all(|&c| c == 0x0f));
+}
+
+/// 生成した迷路が規定を満たしているか
+fn check_generated(maze: &Maze<16, 16>) -> usize {
+    assert!(maze.has_wall(Point { x: 0, y: 0 }, Direction::Right));
+    assert!(!maze.has_wall(Point { x: 0, y: 0 }, Direction::Up));
+    for py in 1..16 {
+        for px in 1..16 {
+            if (px, py) != (8, 8) {
+                assert!(maze.post_walls(px, py) > 0, "({}, {})", px, py);
+            }
+        }
+    }
+    let mut entrances = 0;
+    for g in maze.goal.cells() {
+        for &dir in [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ]
+        .iter()
+        {
+            if !maze.goal.contains(g.get_around(dir)) && !maze.has_wall(g, dir) {
+                entrances += 1;
+            }
+        }
+    }
+    assert_eq!(entrances, 1);
+    // すべての区画にたどり着ける
+    let m = Explorer::<16, 16>::from_maze(maze);
+    let step_map = m.compute_step_map(Connectivity::Four);
+    assert!(step_map
+        .steps
+        .iter()
+        .flatten()
+        .all(|&s| s != STEP_UNREACHABLE));
+
+    let mut open_walls = 0;
+    for y in 0..16 {
+        for x in 0..16 {
+            let p = Point { x, y };
+            open_walls += [Direction::Up, Direction::Right]
+                .iter()
+                .filter(|&&dir| !maze.has_wall(p, dir))
+                .count();
+        }
+    }
+    open_walls
+}
+
+/// 迷路生成
+#[test]
+pub fn generate_maze() {
+    let a = Maze::<16, 16>::generate(1, 0);
+    let b = Maze::<16, 16>::generate(1, 0);
+    let c = Maze::<16, 16>::generate(2, 0);
+    assert_eq!(a.walls, b.walls);
+    assert_ne!(a.walls, c.walls);
+    assert!(a.goal.contains(Point { x: 7, y: 7 }));
+    assert!(a.goal.contains(Point { x: 8, y: 8 }));
+
+    for seed in 0..8 {
+        // 閉路がなければ全域木 + ゴール内の閉路1つ
+        assert_eq!(check_generated(&Maze::<16, 16>::generate(seed, 0)), 256);
+        assert!(check_generated(&Maze::<16, 16>::generate(seed, 20)) > 256);
+    }
+}